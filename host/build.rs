@@ -0,0 +1,56 @@
+use std::{
+    env,
+    path::PathBuf,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+fn main() {
+    // Provenance information reported by `GET /version`, the commit can be passed in
+    // explicitly when building without the git history (e.g. in docker)
+    let git_commit = env::var("RAIKO_GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(git_commit)
+        .unwrap_or_else(|| "unknown".to_string());
+    let build_timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time went backwards")
+        .as_secs();
+    println!("cargo:rustc-env=RAIKO_GIT_COMMIT={git_commit}");
+    println!("cargo:rustc-env=RAIKO_BUILD_TIMESTAMP={build_timestamp}");
+    println!("cargo:rerun-if-env-changed=RAIKO_GIT_COMMIT");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+    println!("cargo:rerun-if-changed=../.git/packed-refs");
+    // Without these the timestamp would only be refreshed on a new commit
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=../lib/src");
+    // The guests the reported ids are derived from
+    println!("cargo:rerun-if-changed=../provers/sp1/guest/elf/riscv32im-succinct-zkvm-elf");
+    println!("cargo:rerun-if-changed=../provers/risc0/guest/src");
+    println!("cargo:rerun-if-changed=../provers/sgx/guest/src");
+    // The SGX setup signs the guest next to the host binary
+    if let Some(target_dir) = target_dir() {
+        println!(
+            "cargo:rerun-if-changed={}",
+            target_dir.join("sgx-guest.sig").display()
+        );
+    }
+}
+
+/// The directory the host binary is built into, `OUT_DIR` is `<target_dir>/build/<pkg>/out`.
+fn target_dir() -> Option<PathBuf> {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").ok()?);
+    out_dir.ancestors().nth(3).map(PathBuf::from)
+}
+
+fn git_commit() -> Option<String> {
+    Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+}
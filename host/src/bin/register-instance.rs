@@ -0,0 +1,498 @@
+//! Builds the Safe transaction registering a bootstrapped SGX instance on the SGX verifier,
+//! for teams whose L1 operations have to go through a multisig. Nothing is signed or sent,
+//! the owners import the transaction and sign it in the Safe app.
+
+use std::str::FromStr;
+
+use alloy_primitives::{b256, Address, Bytes, FixedBytes, B256, U256};
+use alloy_sol_types::{sol, SolCall, SolValue};
+use anyhow::{anyhow, ensure, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use raiko_lib::consts::{get_network_spec, Network};
+use raiko_primitives::keccak::keccak;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use structopt::StructOpt;
+
+sol! {
+    // The parsed DCAP v3 quote the SGX verifier takes, see `V3Struct.sol`
+    #[derive(Debug, PartialEq, Eq)]
+    struct Header {
+        bytes2 version;
+        bytes2 attestationKeyType;
+        bytes4 teeType;
+        bytes2 qeSvn;
+        bytes2 pceSvn;
+        bytes16 qeVendorId;
+        bytes20 userData;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct EnclaveReport {
+        bytes16 cpuSvn;
+        bytes4 miscSelect;
+        bytes28 reserved1;
+        bytes16 attributes;
+        bytes32 mrEnclave;
+        bytes32 reserved2;
+        bytes32 mrSigner;
+        bytes reserved3;
+        uint16 isvProdId;
+        uint16 isvSvn;
+        bytes reserved4;
+        bytes reportData;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct QEAuthData {
+        uint16 parsedDataSize;
+        bytes data;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct CertificationData {
+        uint16 certType;
+        uint32 certDataSize;
+        bytes[3] decodedCertDataArray;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct ECDSAQuoteV3AuthData {
+        bytes ecdsa256BitSignature;
+        bytes ecdsaAttestationKey;
+        EnclaveReport pckSignedQeReport;
+        bytes qeReportSignature;
+        QEAuthData qeAuthData;
+        CertificationData certification;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct ParsedV3QuoteStruct {
+        Header header;
+        EnclaveReport localEnclaveReport;
+        ECDSAQuoteV3AuthData v3AuthData;
+    }
+
+    function registerInstance(ParsedV3QuoteStruct _attestation) external returns (uint256);
+}
+
+/// keccak256("EIP712Domain(uint256 chainId,address verifyingContract)")
+pub const DOMAIN_SEPARATOR_TYPEHASH: B256 =
+    b256!("47e79534a245952e8b16893a336b85a3d9ea9fa8c573f3d803afb92a79469218");
+/// keccak256("SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,
+/// uint256 baseGas,uint256 gasPrice,address gasToken,address refundReceiver,uint256 nonce)")
+pub const SAFE_TX_TYPEHASH: B256 =
+    b256!("bb8310d486368db6bd6f849402fdd73ad53d316b5a4b2644ad6efe0f941286d8");
+
+const QUOTE_HEADER_LENGTH: usize = 48;
+const ENCLAVE_REPORT_LENGTH: usize = 384;
+/// Same as `V3Parser.MINIMUM_QUOTE_LENGTH`
+const MINIMUM_QUOTE_LENGTH: usize = 1020;
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+#[derive(StructOpt, Debug)]
+pub struct Opt {
+    #[structopt(long, require_equals = true, default_value = "taiko_a7")]
+    /// The network whose SGX verifier the instance is registered on
+    network: String,
+
+    #[structopt(long, require_equals = true)]
+    /// The quote of the bootstrapped instance, `quote` in the bootstrap output
+    quote: Bytes,
+
+    #[structopt(long, require_equals = true)]
+    /// The Safe owning the registration
+    safe: Address,
+
+    #[structopt(long, require_equals = true)]
+    /// The chain the Safe is deployed on
+    chain_id: u64,
+
+    #[structopt(long, require_equals = true)]
+    /// The nonce of the Safe tx, the next nonce shown for the Safe in the Safe app
+    nonce: u64,
+}
+
+/// A call from the Safe, the fields of `execTransaction`.
+#[serde_as]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeTx {
+    #[serde_as(as = "DisplayFromStr")]
+    pub to: Address,
+    #[serde_as(as = "DisplayFromStr")]
+    pub value: U256,
+    pub data: Bytes,
+    /// 0 for a call, 1 for a delegate call
+    pub operation: u8,
+    #[serde_as(as = "DisplayFromStr")]
+    pub safe_tx_gas: U256,
+    #[serde_as(as = "DisplayFromStr")]
+    pub base_gas: U256,
+    #[serde_as(as = "DisplayFromStr")]
+    pub gas_price: U256,
+    #[serde_as(as = "DisplayFromStr")]
+    pub gas_token: Address,
+    #[serde_as(as = "DisplayFromStr")]
+    pub refund_receiver: Address,
+    pub nonce: u64,
+}
+
+impl SafeTx {
+    /// The EIP-712 hash the owners sign, same as `getTransactionHash` on the Safe.
+    pub fn hash(&self, safe: Address, chain_id: u64) -> B256 {
+        let domain_separator: B256 =
+            keccak((DOMAIN_SEPARATOR_TYPEHASH, U256::from(chain_id), safe).abi_encode()).into();
+        let struct_hash: B256 = keccak(
+            (
+                SAFE_TX_TYPEHASH,
+                self.to,
+                self.value,
+                B256::from(keccak(&self.data)),
+                self.operation,
+                self.safe_tx_gas,
+                self.base_gas,
+                self.gas_price,
+                self.gas_token,
+                self.refund_receiver,
+                U256::from(self.nonce),
+            )
+                .abi_encode(),
+        )
+        .into();
+        keccak(
+            [
+                &[0x19, 0x01],
+                domain_separator.as_slice(),
+                struct_hash.as_slice(),
+            ]
+            .concat(),
+        )
+        .into()
+    }
+}
+
+/// The unsigned Safe tx and the hash the owners sign, `safeTxHash` is the hash the Safe app
+/// shows for the transaction once it is imported.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeTxProposal {
+    #[serde_as(as = "DisplayFromStr")]
+    pub safe: Address,
+    pub chain_id: u64,
+    #[serde(flatten)]
+    pub tx: SafeTx,
+    pub safe_tx_hash: B256,
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::from_args();
+    let network = Network::from_str(&opt.network)?;
+    let proposal = build_registration(network, &opt.quote, opt.safe, opt.chain_id, opt.nonce)?;
+    println!("{}", serde_json::to_string_pretty(&proposal)?);
+    Ok(())
+}
+
+/// Builds the Safe tx calling `registerInstance` on the SGX verifier of the network with the
+/// parsed quote.
+pub fn build_registration(
+    network: Network,
+    quote: &[u8],
+    safe: Address,
+    chain_id: u64,
+    nonce: u64,
+) -> Result<SafeTxProposal> {
+    let sgx_verifier = get_network_spec(network)
+        .sgx_verifier_address
+        .ok_or_else(|| anyhow!("no SGX verifier on {network:?}"))?;
+    let attestation = parse_quote(quote)?;
+    let tx = SafeTx {
+        to: sgx_verifier,
+        data: registerInstanceCall {
+            _attestation: attestation,
+        }
+        .abi_encode()
+        .into(),
+        nonce,
+        ..Default::default()
+    };
+    Ok(SafeTxProposal {
+        safe,
+        chain_id,
+        safe_tx_hash: tx.hash(safe, chain_id),
+        tx,
+    })
+}
+
+/// Parses the DCAP v3 quote into the struct `registerInstance` takes, the same way
+/// `V3Parser.parseInput` does onchain.
+pub fn parse_quote(quote: &[u8]) -> Result<ParsedV3QuoteStruct> {
+    ensure!(
+        quote.len() > MINIMUM_QUOTE_LENGTH,
+        "quote of {} bytes is too short",
+        quote.len()
+    );
+    let auth_data_offset = QUOTE_HEADER_LENGTH + ENCLAVE_REPORT_LENGTH + 4;
+    let auth_data_size = le_u32(slice(quote, auth_data_offset - 4, 4)?) as usize;
+    ensure!(
+        quote.len() - auth_data_offset == auth_data_size,
+        "quote auth data size mismatch, expected {auth_data_size}, got {}",
+        quote.len() - auth_data_offset
+    );
+    let header = Header {
+        version: fixed(quote, 0)?,
+        attestationKeyType: fixed(quote, 2)?,
+        teeType: fixed(quote, 4)?,
+        qeSvn: fixed(quote, 8)?,
+        pceSvn: fixed(quote, 10)?,
+        qeVendorId: fixed(quote, 12)?,
+        userData: fixed(quote, 28)?,
+    };
+    let local_enclave_report =
+        parse_enclave_report(slice(quote, QUOTE_HEADER_LENGTH, ENCLAVE_REPORT_LENGTH)?)?;
+
+    let auth_data = &quote[auth_data_offset..];
+    let qe_auth_data_size = le_u16(slice(auth_data, 576, 2)?);
+    let qe_auth_data = QEAuthData {
+        parsedDataSize: qe_auth_data_size,
+        data: slice(auth_data, 578, qe_auth_data_size as usize)?
+            .to_vec()
+            .into(),
+    };
+    let offset = 578 + qe_auth_data_size as usize;
+    let cert_type = le_u16(slice(auth_data, offset, 2)?);
+    ensure!(
+        (1..=5).contains(&cert_type),
+        "unsupported certification data type {cert_type}"
+    );
+    let cert_data_size = le_u32(slice(auth_data, offset + 2, 4)?);
+    let cert_data = slice(auth_data, offset + 6, cert_data_size as usize)?;
+    let certification = CertificationData {
+        certType: cert_type,
+        certDataSize: cert_data_size,
+        decodedCertDataArray: split_certificate_chain(cert_data)?,
+    };
+
+    Ok(ParsedV3QuoteStruct {
+        header,
+        localEnclaveReport: local_enclave_report,
+        v3AuthData: ECDSAQuoteV3AuthData {
+            ecdsa256BitSignature: slice(auth_data, 0, 64)?.to_vec().into(),
+            ecdsaAttestationKey: slice(auth_data, 64, 64)?.to_vec().into(),
+            pckSignedQeReport: parse_enclave_report(slice(auth_data, 128, ENCLAVE_REPORT_LENGTH)?)?,
+            qeReportSignature: slice(auth_data, 512, 64)?.to_vec().into(),
+            qeAuthData: qe_auth_data,
+            certification,
+        },
+    })
+}
+
+fn parse_enclave_report(report: &[u8]) -> Result<EnclaveReport> {
+    Ok(EnclaveReport {
+        cpuSvn: fixed(report, 0)?,
+        miscSelect: fixed(report, 16)?,
+        reserved1: fixed(report, 20)?,
+        attributes: fixed(report, 48)?,
+        mrEnclave: fixed(report, 64)?,
+        reserved2: fixed(report, 96)?,
+        mrSigner: fixed(report, 128)?,
+        reserved3: slice(report, 160, 96)?.to_vec().into(),
+        isvProdId: le_u16(slice(report, 256, 2)?),
+        isvSvn: le_u16(slice(report, 258, 2)?),
+        reserved4: slice(report, 260, 60)?.to_vec().into(),
+        reportData: slice(report, 320, 64)?.to_vec().into(),
+    })
+}
+
+/// Splits the PEM certificate chain of the quote into the DER encoded certificates.
+fn split_certificate_chain(cert_data: &[u8]) -> Result<[Bytes; 3]> {
+    let mut pem = core::str::from_utf8(cert_data)
+        .context("certificate chain is not PEM encoded")?
+        .trim_end_matches('\0');
+    let mut certs = Vec::new();
+    while let Some(begin) = pem.find(PEM_BEGIN) {
+        let cert = &pem[begin + PEM_BEGIN.len()..];
+        let end = cert
+            .find(PEM_END)
+            .ok_or_else(|| anyhow!("unterminated certificate in the chain"))?;
+        let base64 = cert[..end]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+        certs.push(Bytes::from(STANDARD.decode(base64)?));
+        pem = &cert[end + PEM_END.len()..];
+    }
+    certs.try_into().map_err(|certs: Vec<Bytes>| {
+        anyhow!("expected 3 certificates in the chain, got {}", certs.len())
+    })
+}
+
+fn slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    data.get(offset..offset + len)
+        .ok_or_else(|| anyhow!("quote too short to read {len} bytes at {offset}"))
+}
+
+fn fixed<const N: usize>(data: &[u8], offset: usize) -> Result<FixedBytes<N>> {
+    Ok(FixedBytes::from_slice(slice(data, offset, N)?))
+}
+
+fn le_u16(data: &[u8]) -> u16 {
+    u16::from_le_bytes([data[0], data[1]])
+}
+
+fn le_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::address;
+
+    use super::*;
+
+    const SAFE: Address = address!("5FbDB2315678afecb367f032d93F642f64180aa3");
+
+    #[test]
+    fn test_safe_typehashes() {
+        assert_eq!(
+            B256::from(keccak(
+                "EIP712Domain(uint256 chainId,address verifyingContract)"
+            )),
+            DOMAIN_SEPARATOR_TYPEHASH
+        );
+        assert_eq!(
+            B256::from(keccak(
+                "SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,\
+                 uint256 baseGas,uint256 gasPrice,address gasToken,address refundReceiver,\
+                 uint256 nonce)"
+            )),
+            SAFE_TX_TYPEHASH
+        );
+    }
+
+    #[test]
+    fn test_safe_tx_hash() {
+        let tx = SafeTx {
+            to: address!("532EFBf6D62720D0B2a2Bb9d11066E8588cAE6D9"),
+            value: U256::from(1),
+            data: Bytes::from(vec![0xde, 0xad, 0xbe, 0xef]),
+            nonce: 7,
+            ..Default::default()
+        };
+        // Computed independently from the EIP-712 encoding of the Safe contracts
+        assert_eq!(
+            tx.hash(SAFE, 17000),
+            b256!("0ccf2cfd7ef77f014cb8e77ca607ca38e12496a19a94f1827d68fe3c9379d754")
+        );
+        assert_ne!(tx.hash(SAFE, 17000), tx.hash(SAFE, 1));
+        assert_ne!(tx.hash(SAFE, 17000), tx.hash(Address::ZERO, 17000));
+        let next = SafeTx {
+            nonce: 8,
+            ..tx.clone()
+        };
+        assert_ne!(tx.hash(SAFE, 17000), next.hash(SAFE, 17000));
+    }
+
+    /// A quote with the layout of a DCAP v3 quote, every field filled with its offset.
+    fn test_quote(certs: usize) -> Vec<u8> {
+        let pem = (0..certs)
+            .map(|i| {
+                format!(
+                    "{PEM_BEGIN}\n{}\n{PEM_END}\n",
+                    STANDARD.encode([i as u8; 40])
+                )
+            })
+            .collect::<String>();
+        let mut auth_data = (0..576).map(|i| i as u8).collect::<Vec<u8>>();
+        auth_data.extend(2u16.to_le_bytes());
+        auth_data.extend([0xaa, 0xbb]);
+        auth_data.extend(5u16.to_le_bytes());
+        auth_data.extend((pem.len() as u32).to_le_bytes());
+        auth_data.extend(pem.as_bytes());
+
+        let mut quote = (0..QUOTE_HEADER_LENGTH + ENCLAVE_REPORT_LENGTH)
+            .map(|i| i as u8)
+            .collect::<Vec<u8>>();
+        quote.extend((auth_data.len() as u32).to_le_bytes());
+        quote.extend(auth_data);
+        quote
+    }
+
+    #[test]
+    fn test_parse_quote() {
+        let quote = test_quote(3);
+        let parsed = parse_quote(&quote).unwrap();
+        assert_eq!(parsed.header.version, FixedBytes::from([0, 1]));
+        assert_eq!(
+            parsed.header.userData,
+            FixedBytes::from_slice(&quote[28..48])
+        );
+        assert_eq!(
+            parsed.localEnclaveReport.mrEnclave,
+            FixedBytes::from_slice(&quote[48 + 64..48 + 96])
+        );
+        assert_eq!(
+            parsed.localEnclaveReport.isvProdId,
+            u16::from_le_bytes([quote[48 + 256], quote[48 + 257]])
+        );
+        assert_eq!(
+            parsed.v3AuthData.qeAuthData.data,
+            Bytes::from(vec![0xaa, 0xbb])
+        );
+        assert_eq!(parsed.v3AuthData.certification.certType, 5);
+        assert_eq!(
+            parsed.v3AuthData.certification.decodedCertDataArray,
+            [0u8, 1, 2].map(|i| Bytes::from(vec![i; 40]))
+        );
+
+        // The attestation round trips through the registerInstance calldata
+        let call = registerInstanceCall {
+            _attestation: parsed,
+        }
+        .abi_encode();
+        assert_eq!(
+            registerInstanceCall::abi_decode(&call, true)
+                .unwrap()
+                ._attestation,
+            parse_quote(&quote).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_quote() {
+        let quote = test_quote(3);
+        assert!(parse_quote(&quote[..MINIMUM_QUOTE_LENGTH]).is_err());
+        // auth data size doesn't match the quote
+        assert!(parse_quote(&quote[..quote.len() - 1]).is_err());
+        // only 2 certificates
+        assert!(parse_quote(&test_quote(2)).is_err());
+    }
+
+    #[test]
+    fn test_build_registration() {
+        let proposal =
+            build_registration(Network::TaikoA7, &test_quote(3), SAFE, 17000, 3).unwrap();
+        assert_eq!(
+            Some(proposal.tx.to),
+            get_network_spec(Network::TaikoA7).sgx_verifier_address
+        );
+        assert_eq!(
+            proposal.tx.data[..4],
+            registerInstanceCall::SELECTOR,
+            "calls registerInstance"
+        );
+        assert_eq!(proposal.safe_tx_hash, proposal.tx.hash(SAFE, 17000));
+
+        // The Safe app takes checksummed addresses and decimal numbers
+        let json = serde_json::to_value(&proposal).unwrap();
+        assert_eq!(json["safe"], SAFE.to_string());
+        assert_eq!(json["to"], proposal.tx.to.to_string());
+        assert_eq!(json["value"], "0");
+        assert_eq!(json["nonce"], 3);
+
+        // Only the networks with an SGX verifier take registrations
+        assert!(build_registration(Network::Ethereum, &test_quote(3), SAFE, 1, 3).is_err());
+    }
+}
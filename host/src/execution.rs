@@ -1,10 +1,13 @@
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc, time::Duration};
 
+use anyhow::anyhow;
 use raiko_lib::{
-    builder::{BlockBuilderStrategy, TaikoStrategy},
+    builder::{build_batch, build_block},
     consts::Network,
-    input::{GuestInput, GuestOutput, TaikoProverData, WrappedHeader},
-    protocol_instance::{assemble_protocol_instance, ProtocolInstance},
+    input::{GuestBatchInput, GuestInput, GuestOutput, TaikoProverData, WrappedHeader},
+    protocol_instance::{
+        assemble_batch_protocol_instance, assemble_protocol_instance, ProtocolInstance,
+    },
     prover::{to_proof, Proof, Prover, ProverResult},
     taiko_utils::HeaderHasher,
     Measurement,
@@ -14,37 +17,101 @@ use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
 use super::error::Result;
-use crate::{error::HostError, memory, preflight::preflight, request::ProofRequest};
+use crate::{
+    error::HostError,
+    memory,
+    metrics::observe_timings,
+    preflight::{batch_preflight, preflight},
+    request::ProofRequest,
+    scheduler::{proving_job, Scheduler, EXECUTION_JOB, PREFLIGHT_JOB},
+};
+
+/// Wall-clock duration of every stage of the proof generation in milliseconds.
+/// Stages that were not run (e.g. preflight when the input was cached) are not set. The host
+/// doesn't submit proofs itself, so there is no submission stage.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProofTimings {
+    /// Fetching the block data and executing the block against the RPC state
+    pub preflight_ms: Option<u64>,
+    /// Fetching the storage proofs, headers and code and building the tries
+    pub witness_ms: Option<u64>,
+    /// Test run of the guest program on the host
+    pub execution_ms: Option<u64>,
+    /// Running the prover
+    pub proving_ms: Option<u64>,
+    /// Wrapping the proof for onchain verification (e.g. STARK to SNARK), as reported by the
+    /// prover in `wrapping_ms`
+    pub wrapping_ms: Option<u64>,
+}
+
+impl ProofTimings {
+    pub fn stages(&self) -> [(&'static str, Option<u64>); 5] {
+        [
+            ("preflight", self.preflight_ms),
+            ("witness", self.witness_ms),
+            ("execution", self.execution_ms),
+            ("proving", self.proving_ms),
+            ("wrapping", self.wrapping_ms),
+        ]
+    }
+
+    /// Records the time spent in the prover, the wrapping reported by the prover is taken out
+    /// of the proof and out of the proving time.
+    pub fn set_proving(&mut self, prover_time: Duration, proof: &mut Proof) {
+        let wrapping_ms = proof
+            .as_object_mut()
+            .and_then(|proof| proof.remove("wrapping_ms"))
+            .and_then(|wrapping_ms| wrapping_ms.as_u64());
+        self.proving_ms =
+            Some((prover_time.as_millis() as u64).saturating_sub(wrapping_ms.unwrap_or_default()));
+        self.wrapping_ms = wrapping_ms;
+    }
+}
 
 pub async fn execute<D: Prover>(
     config: &serde_json::Value,
     cached_input: Option<GuestInput>,
+    scheduler: &Arc<Scheduler>,
 ) -> Result<(GuestInput, Proof)> {
     let total_proving_time = Measurement::start("", false);
+    let mut timings = ProofTimings::default();
 
     // Generate the input
     let input = if let Some(cached_input) = cached_input {
         println!("Using cached input");
         cached_input
     } else {
+        let _reservation = scheduler.reserve(PREFLIGHT_JOB).await;
         memory::reset_stats();
         let measurement = Measurement::start("Generating input...", false);
-        let input = prepare_input(config).await?;
-        measurement.stop_with("=> Input generated");
+        let (input, witness_time) = prepare_input(config).await?;
+        let input_time = measurement.stop_with("=> Input generated");
         memory::print_stats("Input generation peak memory used: ");
+        timings.preflight_ms = Some(input_time.saturating_sub(witness_time).as_millis() as u64);
+        timings.witness_ms = Some(witness_time.as_millis() as u64);
         input
     };
 
     // 2. Test run the block
-    memory::reset_stats();
-    match TaikoStrategy::build_from(&input) {
+    let build_result = {
+        let _reservation = scheduler.reserve(EXECUTION_JOB).await;
+        memory::reset_stats();
+        let measurement = Measurement::start("", false);
+        let build_result = build_block(&input);
+        timings.execution_ms = Some(measurement.stop_with("=> Block built").as_millis() as u64);
+        build_result
+    };
+    match build_result {
         Ok((header, _mpt_node)) => {
             info!("Verifying final state using provider data ...");
             info!("Final block hash derived successfully. {}", header.hash());
             info!("Final block header derived successfully. {:?}", header);
             let pi = D::instance_hash(assemble_protocol_instance(&input, &header)?);
-            // Make sure the blockhash from the node matches the one from the builder
-            assert_eq!(header.hash().0, input.block_hash, "block hash unexpected");
+            // Make sure the blockhash from the node matches the one from the builder, a
+            // preconfirmed block that isn't on the node yet has nothing to compare with
+            if input.block_hash != B256::ZERO {
+                assert_eq!(header.hash().0, input.block_hash, "block hash unexpected");
+            }
             let output = GuestOutput::Success((
                 WrappedHeader {
                     header: header.clone(),
@@ -54,19 +121,26 @@ pub async fn execute<D: Prover>(
             memory::print_stats("Guest program peak memory used: ");
 
             // Prove
+            let proof_type = config["proof_type"].as_str().unwrap_or_default();
+            let _reservation = scheduler.reserve(proving_job(config)).await;
             memory::reset_stats();
             let measurement = Measurement::start("Generating proof...", false);
-            let res = D::run(input.clone(), output, config)
+            let mut proof = D::run(&input, output, config)
                 .await
-                .map(|proof| (input, proof))
                 .map_err(|e| HostError::GuestError(e.to_string()))?;
 
-            measurement.stop_with("=> Proof generated");
+            timings.set_proving(measurement.stop_with("=> Proof generated"), &mut proof);
             memory::print_stats("Prover peak memory used: ");
 
             total_proving_time.stop_with("====> Complete proof generated");
 
-            Ok(res)
+            // Report where the time was spent
+            observe_timings(proof_type, &timings);
+            if let Some(proof) = proof.as_object_mut() {
+                proof.insert("timings".to_string(), serde_json::to_value(&timings)?);
+            }
+
+            Ok((input, proof))
         }
         Err(e) => {
             warn!("Proving bad block construction!");
@@ -75,8 +149,96 @@ pub async fn execute<D: Prover>(
     }
 }
 
-/// prepare input data for provers
-pub async fn prepare_input(config: &serde_json::Value) -> Result<GuestInput> {
+/// Proves all the blocks of a batch with a single proof. The input of a batch is not cached.
+pub async fn execute_batch<D: Prover>(
+    config: &serde_json::Value,
+    scheduler: &Arc<Scheduler>,
+) -> Result<Proof> {
+    let total_proving_time = Measurement::start("", false);
+    let mut timings = ProofTimings::default();
+
+    // Generate the input
+    let input = {
+        let _reservation = scheduler.reserve(PREFLIGHT_JOB).await;
+        memory::reset_stats();
+        let measurement = Measurement::start("Generating batch input...", false);
+        let (input, witness_time) = prepare_batch_input(config).await?;
+        let input_time = measurement.stop_with("=> Batch input generated");
+        memory::print_stats("Input generation peak memory used: ");
+        timings.preflight_ms = Some(input_time.saturating_sub(witness_time).as_millis() as u64);
+        timings.witness_ms = Some(witness_time.as_millis() as u64);
+        input
+    };
+
+    // 2. Test run the blocks of the batch
+    let build_result = {
+        let _reservation = scheduler.reserve(EXECUTION_JOB).await;
+        memory::reset_stats();
+        let measurement = Measurement::start("", false);
+        let build_result = build_batch(&input);
+        timings.execution_ms = Some(measurement.stop_with("=> Batch built").as_millis() as u64);
+        build_result
+    };
+    let headers = match build_result {
+        Ok(headers) => headers,
+        Err(e) => {
+            warn!("Proving bad batch construction!");
+            return Err(HostError::GuestError(e.to_string()));
+        }
+    };
+    for (header, block_input) in headers.iter().zip(&input.inputs) {
+        // Make sure the blockhashes from the node match the ones from the builder
+        assert_eq!(
+            header.hash().0,
+            block_input.block_hash,
+            "block hash unexpected"
+        );
+    }
+    let pi = D::batch_instance_hash(
+        assemble_batch_protocol_instance(&input, &headers)
+            .map_err(|e| HostError::GuestError(e.to_string()))?,
+    );
+    let last_header = headers.last().expect("batch has blocks").clone();
+    info!(
+        "Final batch block hash derived successfully. {}",
+        last_header.hash()
+    );
+    let output = GuestOutput::Success((
+        WrappedHeader {
+            header: last_header,
+        },
+        pi,
+    ));
+    memory::print_stats("Guest program peak memory used: ");
+
+    // Prove
+    let proof_type = config["proof_type"].as_str().unwrap_or_default();
+    let _reservation = scheduler.reserve(proving_job(config)).await;
+    memory::reset_stats();
+    let measurement = Measurement::start("Generating batch proof...", false);
+    let mut proof = D::run_batch(&input, output, config)
+        .await
+        .map_err(|e| HostError::GuestError(e.to_string()))?;
+
+    timings.set_proving(
+        measurement.stop_with("=> Batch proof generated"),
+        &mut proof,
+    );
+    memory::print_stats("Prover peak memory used: ");
+
+    total_proving_time.stop_with("====> Complete batch proof generated");
+
+    // Report where the time was spent
+    observe_timings(proof_type, &timings);
+    if let Some(proof) = proof.as_object_mut() {
+        proof.insert("timings".to_string(), serde_json::to_value(&timings)?);
+    }
+
+    Ok(proof)
+}
+
+/// prepare input data for provers, also returns the time spent on building the witness
+pub async fn prepare_input(config: &serde_json::Value) -> Result<(GuestInput, Duration)> {
     let req = ProofRequest::deserialize(config).unwrap();
     let block_number = req.block_number;
     let rpc = req.rpc.clone();
@@ -85,6 +247,7 @@ pub async fn prepare_input(config: &serde_json::Value) -> Result<GuestInput> {
     let network = req.network.clone();
     let graffiti = req.graffiti;
     let prover = req.prover;
+    let preconfirmation = req.preconfirmation;
     tokio::task::spawn_blocking(move || {
         preflight(
             Some(rpc),
@@ -93,6 +256,7 @@ pub async fn prepare_input(config: &serde_json::Value) -> Result<GuestInput> {
             TaikoProverData { graffiti, prover },
             Some(l1_rpc),
             Some(beacon_rpc),
+            preconfirmation,
         )
         .expect("Failed to fetch required data for block")
     })
@@ -100,6 +264,38 @@ pub async fn prepare_input(config: &serde_json::Value) -> Result<GuestInput> {
     .map_err(Into::<super::error::HostError>::into)
 }
 
+/// prepare the input data of a batch for provers, also returns the time spent on building the
+/// witness
+pub async fn prepare_batch_input(
+    config: &serde_json::Value,
+) -> Result<(GuestBatchInput, Duration)> {
+    let req = ProofRequest::deserialize(config).unwrap();
+    let batch_id = req.batch_id.ok_or_else(|| anyhow!("batch_id missing"))?;
+    let l1_inclusion_block_number = req
+        .l1_inclusion_block_number
+        .ok_or_else(|| anyhow!("l1_inclusion_block_number required to prove a batch"))?;
+    let rpc = req.rpc.clone();
+    let l1_rpc = req.l1_rpc.clone();
+    let beacon_rpc = req.beacon_rpc.clone();
+    let network = req.network.clone();
+    let graffiti = req.graffiti;
+    let prover = req.prover;
+    tokio::task::spawn_blocking(move || {
+        batch_preflight(
+            Some(rpc),
+            batch_id,
+            l1_inclusion_block_number,
+            Network::from_str(&network).unwrap(),
+            TaikoProverData { graffiti, prover },
+            Some(l1_rpc),
+            Some(beacon_rpc),
+        )
+        .expect("Failed to fetch required data for batch")
+    })
+    .await
+    .map_err(Into::<super::error::HostError>::into)
+}
+
 pub struct NativeDriver;
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -109,7 +305,15 @@ pub struct NativeResponse {
 
 impl Prover for NativeDriver {
     async fn run(
-        _input: GuestInput,
+        _input: &GuestInput,
+        output: GuestOutput,
+        _request: &serde_json::Value,
+    ) -> ProverResult<Proof> {
+        to_proof(Ok(NativeResponse { output }))
+    }
+
+    async fn run_batch(
+        _input: &GuestBatchInput,
         output: GuestOutput,
         _request: &serde_json::Value,
     ) -> ProverResult<Proof> {
@@ -123,6 +327,28 @@ impl Prover for NativeDriver {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use super::ProofTimings;
+
+    #[test]
+    fn test_wrapping_timing() {
+        let mut timings = ProofTimings::default();
+        let mut proof = serde_json::json!({ "proof": "0x", "wrapping_ms": 400 });
+        timings.set_proving(Duration::from_millis(1000), &mut proof);
+        assert_eq!(timings.proving_ms, Some(600));
+        assert_eq!(timings.wrapping_ms, Some(400));
+        assert_eq!(proof, serde_json::json!({ "proof": "0x" }));
+
+        // Provers that don't wrap their proofs
+        let mut timings = ProofTimings::default();
+        let mut proof = serde_json::json!({ "proof": "0x" });
+        timings.set_proving(Duration::from_millis(1000), &mut proof);
+        assert_eq!(timings.proving_ms, Some(1000));
+        assert_eq!(timings.wrapping_ms, None);
+        assert_eq!(timings.stages()[4], ("wrapping", None));
+    }
+
     #[tokio::test]
     async fn test_async_block() {
         let result = async { Result::<(), &'static str>::Err("error") };
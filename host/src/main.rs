@@ -16,10 +16,14 @@
 
 mod error;
 mod execution;
+mod metrics;
+mod policy;
 mod preflight;
 mod provider_db;
 mod request;
+mod scheduler;
 mod server;
+mod version;
 
 use std::{alloc, fmt::Debug, fs::File, io::BufReader, path::PathBuf};
 
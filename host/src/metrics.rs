@@ -0,0 +1,28 @@
+use lazy_static::lazy_static;
+use prometheus::{exponential_buckets, register_histogram_vec, HistogramVec};
+
+use crate::execution::ProofTimings;
+
+lazy_static! {
+    /// Wall-clock duration of every stage of the proof generation, from 100ms to ~1h
+    pub static ref STAGE_DURATION: HistogramVec = register_histogram_vec!(
+        "raiko_stage_duration_seconds",
+        "Wall-clock duration of every stage of the proof generation",
+        &["stage", "proof_type"],
+        exponential_buckets(0.1, 2.0, 16).unwrap()
+    )
+    .unwrap();
+}
+
+/// Records the durations of all the stages that were run for a proof.
+pub fn observe_timings(proof_type: &str, timings: &ProofTimings) {
+    let stages = timings
+        .stages()
+        .into_iter()
+        .filter_map(|(stage, duration_ms)| duration_ms.map(|duration_ms| (stage, duration_ms)));
+    for (stage, duration_ms) in stages {
+        STAGE_DURATION
+            .with_label_values(&[stage, proof_type])
+            .observe(duration_ms as f64 / 1000.0);
+    }
+}
@@ -0,0 +1,172 @@
+use alloy_provider::{ProviderBuilder, RootProvider};
+use alloy_sol_types::SolValue;
+use anyhow::anyhow;
+use raiko_primitives::{keccak::keccak, Address, B256, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, preflight::get_block, request::ProofRequest};
+
+/// The sampling rate is expressed in basis points of all blocks.
+pub const SAMPLING_RATE_PRECISION: u64 = 10_000;
+
+/// Policy for provers that only prove a pseudo-random subset of the blocks.
+///
+/// The selection of a block is fully deterministic: anyone that knows the block hash, the
+/// prover address and the seed can recompute the ballot and check that the block was (or
+/// was not) supposed to be proven.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SamplingPolicy {
+    /// The number of blocks out of every `SAMPLING_RATE_PRECISION` blocks to prove
+    pub rate_bps: u64,
+    /// Extra entropy so operators sharing a prover address can sample different subsets
+    #[serde(default)]
+    pub seed: B256,
+}
+
+/// The outcome of a sampling decision, returned together with the proof.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ballot {
+    pub block_hash: B256,
+    pub prover: Address,
+    pub seed: B256,
+    pub rate_bps: u64,
+    /// keccak(abi.encode(seed, block_hash, prover))
+    pub ballot: B256,
+    pub selected: bool,
+}
+
+impl SamplingPolicy {
+    /// Reads the sampling policy from the `sampling` config entry, if any.
+    pub fn from_config(config: &serde_json::Value) -> Result<Option<Self>> {
+        let policy = match config.get("sampling") {
+            Some(sampling) if !sampling.is_null() => Self::deserialize(sampling)?,
+            _ => return Ok(None),
+        };
+        if policy.rate_bps > SAMPLING_RATE_PRECISION {
+            return Err(anyhow!(
+                "sampling rate of {} bps is above {SAMPLING_RATE_PRECISION} bps",
+                policy.rate_bps
+            )
+            .into());
+        }
+        Ok(Some(policy))
+    }
+
+    /// Computes the ballot for the given block and prover.
+    pub fn ballot(&self, block_hash: B256, prover: Address) -> Ballot {
+        let ballot: B256 = keccak((self.seed, block_hash, prover).abi_encode()).into();
+        let draw = U256::from_be_bytes(ballot.0) % U256::from(SAMPLING_RATE_PRECISION);
+        Ballot {
+            block_hash,
+            prover,
+            seed: self.seed,
+            rate_bps: self.rate_bps,
+            ballot,
+            selected: draw < U256::from(self.rate_bps),
+        }
+    }
+
+    /// Fetches the hash of the requested block and computes its ballot.
+    pub async fn cast_ballot(&self, req: &ProofRequest) -> Result<Ballot> {
+        let rpc = req.rpc.clone();
+        let block_number = req.block_number;
+        let block_hash = tokio::task::spawn_blocking(move || {
+            let url = reqwest::Url::parse(&rpc).map_err(|e| anyhow!("invalid rpc url: {e}"))?;
+            let provider = ProviderBuilder::new().provider(RootProvider::new_http(url));
+            get_block(&provider, block_number, false)?
+                .header
+                .hash
+                .ok_or_else(|| anyhow!("no hash for block {block_number}"))
+        })
+        .await??;
+        Ok(self.ballot(block_hash, req.prover))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, b256};
+
+    use super::*;
+
+    const PROVER: Address = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+
+    fn block_hash(i: u64) -> B256 {
+        keccak(i.to_be_bytes()).into()
+    }
+
+    #[test]
+    fn test_ballot_is_deterministic() {
+        let policy = SamplingPolicy {
+            rate_bps: 5_000,
+            seed: B256::ZERO,
+        };
+        let hash = b256!("e2105909de032b913abfa4c8b6101f9863d82be109ef32890b771ae214784efa");
+        assert_eq!(policy.ballot(hash, PROVER), policy.ballot(hash, PROVER));
+    }
+
+    #[test]
+    fn test_ballot_rate_bounds() {
+        let none = SamplingPolicy {
+            rate_bps: 0,
+            seed: B256::ZERO,
+        };
+        let all = SamplingPolicy {
+            rate_bps: SAMPLING_RATE_PRECISION,
+            seed: B256::ZERO,
+        };
+        for i in 0..100 {
+            assert!(!none.ballot(block_hash(i), PROVER).selected);
+            assert!(all.ballot(block_hash(i), PROVER).selected);
+        }
+    }
+
+    #[test]
+    fn test_ballot_depends_on_seed_and_prover() {
+        let policy = SamplingPolicy {
+            rate_bps: 1_000,
+            seed: B256::ZERO,
+        };
+        let other_seed = SamplingPolicy {
+            seed: B256::with_last_byte(1),
+            ..policy.clone()
+        };
+        let hash = block_hash(0);
+        assert_ne!(
+            policy.ballot(hash, PROVER).ballot,
+            other_seed.ballot(hash, PROVER).ballot
+        );
+        assert_ne!(
+            policy.ballot(hash, PROVER).ballot,
+            policy.ballot(hash, Address::ZERO).ballot
+        );
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let config = serde_json::json!({ "sampling": { "rate_bps": 500 } });
+        let policy = SamplingPolicy::from_config(&config).unwrap().unwrap();
+        assert_eq!(policy.rate_bps, 500);
+        assert_eq!(policy.seed, B256::ZERO);
+
+        assert!(SamplingPolicy::from_config(&serde_json::json!({}))
+            .unwrap()
+            .is_none());
+
+        let config = serde_json::json!({ "sampling": { "rate_bps": SAMPLING_RATE_PRECISION + 1 } });
+        assert!(SamplingPolicy::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_ballot_sampling_rate() {
+        let policy = SamplingPolicy {
+            rate_bps: 2_500,
+            seed: B256::ZERO,
+        };
+        let selected = (0..4_000)
+            .filter(|i| policy.ballot(block_hash(*i), PROVER).selected)
+            .count();
+        // 25% of the blocks, with some slack for randomness
+        assert!((800..1_200).contains(&selected), "selected {selected}");
+    }
+}
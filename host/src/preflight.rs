@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use alloy_consensus::{
     SignableTransaction, TxEip1559, TxEip2930, TxEip4844, TxEip4844Variant, TxEnvelope, TxLegacy,
@@ -6,31 +9,38 @@ use alloy_consensus::{
 pub use alloy_primitives::*;
 use alloy_provider::{Provider, ProviderBuilder, ReqwestProvider, RootProvider};
 use alloy_rpc_types::{
-    Block as AlloyBlock, BlockTransactions, Filter, Transaction as AlloyRpcTransaction,
+    Block as AlloyBlock, BlockNumberOrTag, BlockTransactions, Filter,
+    Transaction as AlloyRpcTransaction,
 };
 use alloy_sol_types::{SolCall, SolEvent};
-use anyhow::{anyhow, bail, Result};
+use anyhow::{anyhow, bail, ensure, Result};
 use c_kzg::{Blob, KzgCommitment};
 use hashbrown::HashSet;
 use raiko_lib::{
     builder::{
-        prepare::TaikoHeaderPrepStrategy, BlockBuilder, OptimisticDatabase, TkoTxExecStrategy,
+        prepare::{EthHeaderPrepStrategy, HeaderPrepStrategy, TaikoHeaderPrepStrategy},
+        BlockBuilder, OptimisticDatabase, TkoTxExecStrategy,
     },
     consts::{get_network_spec, Network},
     input::{
         decode_anchor, proposeBlockCall, taiko_a6::BlockProposed as TestnetBlockProposed,
-        BlockProposed, GuestInput, TaikoGuestInput, TaikoProverData,
+        taiko_batch::BatchProposed, BlockMetadata, BlockProposed, GuestBatchInput, GuestInput,
+        TaikoGuestInput, TaikoProverData,
+    },
+    taiko_utils::{
+        blob_proofs, blob_versioned_hash, block_tx_list, decode_batch_transactions,
+        generate_transactions, to_header, ANCHOR_GAS_LIMIT, BLOB_DATA_CAPACITY,
     },
-    taiko_utils::{generate_transactions, to_header},
     Measurement,
 };
 use raiko_primitives::{
     eip4844::{kzg_to_versioned_hash, MAINNET_KZG_TRUSTED_SETUP},
+    keccak::keccak,
     mpt::proofs_to_tries,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::provider_db::ProviderDb;
+use crate::{provider_db::ProviderDb, request::Preconfirmation};
 
 pub fn preflight(
     rpc_url: Option<String>,
@@ -39,7 +49,8 @@ pub fn preflight(
     prover_data: TaikoProverData,
     l1_rpc_url: Option<String>,
     beacon_rpc_url: Option<String>,
-) -> Result<GuestInput> {
+    preconfirmation: Option<Preconfirmation>,
+) -> Result<(GuestInput, Duration)> {
     let provider = ProviderBuilder::new().provider(RootProvider::new_http(
         reqwest::Url::parse(&rpc_url.clone().unwrap()).expect("invalid rpc url"),
     ));
@@ -47,13 +58,34 @@ pub fn preflight(
 
     let measurement = Measurement::start("Fetching block data...", true);
 
-    let block = get_block(&provider, block_number, true).unwrap();
-    let parent_block = get_block(&provider, block_number - 1, false).unwrap();
+    if preconfirmation.is_some() && !network.is_taiko() {
+        bail!("preconfirmations are only supported for Taiko blocks");
+    }
 
-    println!("\nblock.hash: {:?}", block.header.hash.unwrap());
-    println!("block.parent_hash: {:?}", block.header.parent_hash);
-    println!("block gas used: {:?}", block.header.gas_used.as_limbs()[0]);
-    println!("block transactions: {:?}", block.transactions.len());
+    let parent_block = get_block(&provider, block_number - 1, false)?;
+    // A preconfirmed block doesn't have to be on the L2 node yet, it is built on top of the
+    // current head then
+    let block = match get_block(&provider, block_number, true) {
+        Ok(block) => Some(block),
+        Err(_) if preconfirmation.is_some() => {
+            let head = get_head_block(&provider)?;
+            ensure!(
+                head.header.hash == parent_block.header.hash,
+                "block {block_number} is not available and its parent is not the head {:?}",
+                head.header.number
+            );
+            println!("\nblock {block_number} not available yet, building it on top of the head");
+            None
+        }
+        Err(e) => return Err(e),
+    };
+
+    if let Some(block) = &block {
+        println!("\nblock.hash: {:?}", block.header.hash.unwrap());
+        println!("block.parent_hash: {:?}", block.header.parent_hash);
+        println!("block gas used: {:?}", block.header.gas_used.as_limbs()[0]);
+        println!("block transactions: {:?}", block.transactions.len());
+    }
 
     let taiko_guest_input = if network.is_taiko() {
         let provider_l1 = ProviderBuilder::new().provider(RootProvider::new_http(
@@ -61,9 +93,17 @@ pub fn preflight(
         ));
 
         // Decode the anchor tx to find out which L1 blocks we need to fetch
-        let anchor_tx = match &block.transactions {
-            BlockTransactions::Full(txs) => txs[0].to_owned(),
-            _ => unreachable!(),
+        let anchor_tx = match &block {
+            Some(block) => match &block.transactions {
+                BlockTransactions::Full(txs) => txs[0].to_owned(),
+                _ => unreachable!(),
+            },
+            None => preconfirmation
+                .as_ref()
+                .and_then(|preconfirmation| preconfirmation.anchor_tx.clone())
+                .ok_or_else(|| {
+                    anyhow!("block {block_number} is not available yet, the anchor tx is required")
+                })?,
         };
         let anchor_call = decode_anchor(anchor_tx.input.as_ref())?;
         // The L1 blocks we need
@@ -74,87 +114,236 @@ pub fn preflight(
         println!("anchor L1 state root: {:?}", anchor_call.l1StateRoot);
 
         // Get the L1 state block header so that we can prove the L1 state root
-        let l1_inclusion_block = get_block(&provider_l1, l1_inclusion_block_number, false).unwrap();
         let l1_state_block = get_block(&provider_l1, l1_state_block_number, false).unwrap();
         println!(
             "l1_state_root_block hash: {:?}",
             l1_state_block.header.hash.unwrap()
         );
 
-        // Get the block proposal data
-        let (proposal_tx, proposal_event) = get_block_proposed_event(
-            &provider_l1,
-            network,
-            l1_inclusion_block.header.hash.unwrap(),
-            block_number,
-        )?;
+        let (proposal_event, tx_list, tx_blob_hashes) =
+            if let Some(preconfirmation) = preconfirmation {
+                // The block isn't proposed yet so the proposal data comes with the request
+                println!("preconfirmed block, using the proposal data from the request");
+                ensure!(
+                    preconfirmation.meta.id == block_number,
+                    "preconfirmation is for block {}, not {block_number}",
+                    preconfirmation.meta.id
+                );
+                let (tx_list, tx_blob_hashes) = get_preconfirmed_tx_list(&preconfirmation)?;
+                let proposal_event = BlockProposed {
+                    blockId: U256::from(block_number),
+                    assignedProver: prover_data.prover,
+                    meta: preconfirmation.meta,
+                    ..Default::default()
+                };
+                (proposal_event, tx_list, tx_blob_hashes)
+            } else {
+                let l1_inclusion_block =
+                    get_block(&provider_l1, l1_inclusion_block_number, false).unwrap();
 
-        // Fetch the tx list
-        let (tx_list, tx_blob_hash) = if proposal_event.meta.blobUsed {
-            println!("blob active");
-            // Get the blob hashes attached to the propose tx
-            let blob_hashs = proposal_tx.blob_versioned_hashes.unwrap_or_default();
-            assert!(!blob_hashs.is_empty());
-            // Currently the protocol enforces the first blob hash to be used
-            let blob_hash = blob_hashs[0];
-            let l2_chain_spec = get_network_spec(network);
-            // Get the blob data for this block
-            let slot_id = block_time_to_block_slot(
-                l1_inclusion_block.header.timestamp.as_limbs()[0],
-                l2_chain_spec.genesis_time,
-                l2_chain_spec.seconds_per_slot,
-            )?;
-            let blobs = get_blob_data(&beacon_rpc_url.clone().unwrap(), slot_id)?;
-            assert!(!blobs.data.is_empty(), "blob data not available anymore");
-            // Get the blob data for the blob storing the tx list
-            let tx_blob = blobs
-                .data
-                .iter()
-                .find(|blob| {
-                    // calculate from plain blob
-                    blob_hash == calc_blob_versioned_hash(&blob.blob)
-                })
-                .cloned();
-            assert!(tx_blob.is_some());
-            (blob_to_bytes(&tx_blob.unwrap().blob), Some(blob_hash))
-        } else {
-            // Get the tx list data directly from the propose transaction data
-            let proposal_call = proposeBlockCall::abi_decode(&proposal_tx.input, false).unwrap();
-            (proposal_call.txList.as_ref().to_owned(), None)
-        };
+                // Get the block proposal data
+                let (proposal_tx, proposal_event) = get_block_proposed_event(
+                    &provider_l1,
+                    network,
+                    l1_inclusion_block.header.hash.unwrap(),
+                    block_number,
+                )?;
+
+                // Fetch the tx list from wherever the proposer put it
+                let (tx_list, tx_blob_hashes) = get_tx_list(
+                    &proposal_tx,
+                    &proposal_event,
+                    network,
+                    l1_inclusion_block.header.timestamp.as_limbs()[0],
+                    beacon_rpc_url.as_deref(),
+                )?;
+                (proposal_event, tx_list, tx_blob_hashes)
+            };
 
         // Create the transactions from the proposed tx list
         let transactions = generate_transactions(
             proposal_event.meta.blobUsed,
-            &tx_list,
+            block_tx_list(&proposal_event.meta, &tx_list, &tx_blob_hashes),
             Some(anchor_tx.clone()),
         );
         // Do a sanity check using the transactions returned by the node
-        assert!(
-            transactions.len() >= block.transactions.len(),
-            "unexpected number of transactions"
-        );
+        if let Some(block) = &block {
+            assert!(
+                transactions.len() >= block.transactions.len(),
+                "unexpected number of transactions"
+            );
+        }
+
+        // The guest checks the blobs against their versioned hashes with these
+        let tx_blob_proofs = if tx_blob_hashes.is_empty() {
+            Vec::new()
+        } else {
+            blob_proofs(&tx_list)?
+        };
 
         // Create the input struct without the block data set
         TaikoGuestInput {
             l1_header: to_header(&l1_state_block.header),
             tx_list,
             anchor_tx: serde_json::to_string(&anchor_tx).unwrap(),
-            tx_blob_hash,
+            tx_blob_hashes,
+            tx_blob_proofs,
             block_proposed: proposal_event,
             prover_data,
         }
     } else {
         // For Ethereum blocks we just convert the block transactions in a tx_list
         // so that we don't have to supports separate paths.
+        let block = block
+            .as_ref()
+            .expect("only preconfirmed blocks can be missing");
         TaikoGuestInput {
-            tx_list: alloy_rlp::encode(&get_transactions_from_block(&block)),
+            tx_list: alloy_rlp::encode(&get_transactions_from_block(block)),
             ..Default::default()
         }
     };
     measurement.stop();
 
-    let input = GuestInput {
+    let input = match block {
+        Some(block) => get_block_input(network, block, &parent_block, taiko_guest_input),
+        None => get_preconfirmed_block_input(network, &parent_block, taiko_guest_input)?,
+    };
+    build_guest_input(provider, input, is_local)
+}
+
+/// Fetches all the blocks of a batch and builds the input to prove them together, also
+/// returns the time spent on building the witness of all the blocks.
+pub fn batch_preflight(
+    rpc_url: Option<String>,
+    batch_id: u64,
+    l1_inclusion_block_number: u64,
+    network: Network,
+    prover_data: TaikoProverData,
+    l1_rpc_url: Option<String>,
+    beacon_rpc_url: Option<String>,
+) -> Result<(GuestBatchInput, Duration)> {
+    if !network.is_taiko() {
+        bail!("batches are only supported for Taiko blocks");
+    }
+    if !get_network_spec(network).has_batch_protocol() {
+        bail!("{network:?} doesn't propose blocks in batches");
+    }
+    let provider_l1 = ProviderBuilder::new().provider(RootProvider::new_http(
+        reqwest::Url::parse(&l1_rpc_url.clone().unwrap()).expect("invalid rpc url"),
+    ));
+
+    let measurement = Measurement::start("Fetching batch data...", true);
+
+    // Get the batch proposal data
+    let l1_inclusion_block = get_block(&provider_l1, l1_inclusion_block_number, false)?;
+    let batch_proposed = get_batch_proposed_event(
+        &provider_l1,
+        network,
+        l1_inclusion_block.header.hash.unwrap(),
+        batch_id,
+    )?;
+    let info = &batch_proposed.info;
+    println!("batch {batch_id} blocks: {:?}", info.blocks.len());
+    println!("batch {batch_id} last block id: {:?}", info.lastBlockId);
+
+    // Fetch the tx list of the whole batch
+    let tx_list = if info.blobHashes.is_empty() {
+        println!("batch tx list stored in calldata");
+        batch_proposed.txList.to_vec()
+    } else {
+        println!("batch tx list stored in {} blob(s)", info.blobHashes.len());
+        // The blobs can be reused from an earlier proposal, so they have to be fetched from
+        // the block they were created in
+        let blob_block = get_block(&provider_l1, info.blobCreatedIn as u64, false)?;
+        get_blob_tx_list(
+            &info.blobHashes,
+            network,
+            blob_block.header.timestamp.as_limbs()[0],
+            beacon_rpc_url.as_deref(),
+        )?
+    };
+
+    // All the blocks of the batch are anchored to the same L1 block
+    let anchor_block = get_block(&provider_l1, info.anchorBlockId, false)?;
+    let l1_header = to_header(&anchor_block.header);
+    measurement.stop();
+
+    let num_blocks = info.blocks.len() as u64;
+    ensure!(
+        num_blocks > 0 && num_blocks <= info.lastBlockId,
+        "batch {batch_id} has no valid block range"
+    );
+    let first_block_number = info.lastBlockId + 1 - num_blocks;
+
+    // Every block executes the next `numTransactions` transactions of the batch, including
+    // the ones the L2 node skipped as invalid
+    let mut batch_transactions = decode_batch_transactions(&tx_list, info).into_iter();
+
+    let mut inputs = Vec::with_capacity(num_blocks as usize);
+    let mut witness_time = Duration::ZERO;
+    for (block_number, params) in (first_block_number..=info.lastBlockId).zip(&info.blocks) {
+        let provider = ProviderBuilder::new().provider(RootProvider::new_http(
+            reqwest::Url::parse(&rpc_url.clone().unwrap()).expect("invalid rpc url"),
+        ));
+        let is_local = provider.client().is_local();
+
+        let measurement = Measurement::start("Fetching block data...", true);
+        let block = get_block(&provider, block_number, true)?;
+        let parent_block = get_block(&provider, block_number - 1, false)?;
+
+        println!("\nblock.hash: {:?}", block.header.hash.unwrap());
+        println!("block.parent_hash: {:?}", block.header.parent_hash);
+        println!("block transactions: {:?}", block.transactions.len());
+
+        let anchor_tx = match &block.transactions {
+            BlockTransactions::Full(txs) if !txs.is_empty() => txs[0].to_owned(),
+            _ => bail!("block {block_number} does not have an anchor tx"),
+        };
+        let transactions = batch_transactions
+            .by_ref()
+            .take(params.numTransactions as usize)
+            .collect::<Vec<_>>();
+        let taiko_guest_input = TaikoGuestInput {
+            l1_header: l1_header.clone(),
+            tx_list: alloy_rlp::encode(&transactions),
+            anchor_tx: serde_json::to_string(&anchor_tx).unwrap(),
+            prover_data: prover_data.clone(),
+            ..Default::default()
+        };
+        measurement.stop();
+
+        let (input, block_witness_time) = build_guest_input(
+            provider,
+            get_block_input(network, block, &parent_block, taiko_guest_input),
+            is_local,
+        )?;
+        witness_time += block_witness_time;
+        inputs.push(input);
+    }
+
+    let tx_blob_proofs = if batch_proposed.info.blobHashes.is_empty() {
+        Vec::new()
+    } else {
+        blob_proofs(&tx_list)?
+    };
+    let input = GuestBatchInput {
+        inputs,
+        batch_proposed,
+        tx_list,
+        tx_blob_proofs,
+    };
+    Ok((input, witness_time))
+}
+
+/// The input of the block without the witness, taken from the block returned by the node.
+fn get_block_input(
+    network: Network,
+    block: AlloyBlock,
+    parent_block: &AlloyBlock,
+    taiko_guest_input: TaikoGuestInput,
+) -> GuestInput {
+    let block_number: u64 = block.header.number.unwrap().try_into().unwrap();
+    GuestInput {
         network,
         block_number,
         gas_used: block.header.gas_used.try_into().unwrap(),
@@ -183,18 +372,55 @@ pub fn preflight(
         },
         parent_beacon_block_root: block.header.parent_beacon_block_root,
         taiko: taiko_guest_input,
-    };
+    }
+}
 
-    // Create the block builder, run the transactions and extract the DB
-    let provider_db = ProviderDb::new(
-        provider,
+/// The input of a preconfirmed block that is not on the L2 node yet without the witness. The
+/// block is built on top of its parent, the current head, with the header fields taken from
+/// the metadata it will be proposed with. Its hash isn't known before it is built, so it is
+/// left zero.
+fn get_preconfirmed_block_input(
+    network: Network,
+    parent_block: &AlloyBlock,
+    taiko_guest_input: TaikoGuestInput,
+) -> Result<GuestInput> {
+    let meta = &taiko_guest_input.block_proposed.meta;
+    let anchor_tx: AlloyRpcTransaction = serde_json::from_str(&taiko_guest_input.anchor_tx)?;
+    // The anchor tx pays exactly the base fee of the block
+    let base_fee_per_gas = anchor_tx
+        .max_fee_per_gas
+        .ok_or_else(|| anyhow!("anchor tx without max fee per gas"))?;
+    let base_fee_per_gas = u64::try_from(base_fee_per_gas)
+        .map_err(|_| anyhow!("invalid anchor tx max fee per gas {base_fee_per_gas}"))?;
+    Ok(GuestInput {
         network,
-        parent_block.header.number.unwrap().try_into().unwrap(),
-    )?;
+        block_number: meta.id,
+        parent_header: to_header(&parent_block.header),
+        beneficiary: meta.coinbase,
+        gas_limit: meta.gasLimit as u64 + ANCHOR_GAS_LIMIT,
+        timestamp: meta.timestamp,
+        extra_data: meta.extraData.to_vec().into(),
+        mix_hash: meta.difficulty,
+        base_fee_per_gas,
+        taiko: taiko_guest_input,
+        ..Default::default()
+    })
+}
 
-    let mut builder = BlockBuilder::new(&input)
-        .with_db(provider_db)
-        .prepare_header::<TaikoHeaderPrepStrategy>()?;
+/// Executes the block against the RPC state and gathers the witness the guest needs to
+/// execute it again, also returns the time spent on building the witness.
+fn build_guest_input(
+    provider: ReqwestProvider,
+    input: GuestInput,
+    is_local: bool,
+) -> Result<(GuestInput, Duration)> {
+    let network = input.network;
+
+    // Create the block builder, run the transactions and extract the DB
+    let provider_db = ProviderDb::new(provider, network, input.parent_header.number)?;
+
+    let builder = BlockBuilder::new(&input).with_db(provider_db);
+    let mut builder = prepare_header(builder, network)?;
 
     // Optimize data gathering by executing the transactions multiple times so data can be requested in batches
     let max_iterations = if is_local { 1 } else { 50 };
@@ -213,10 +439,11 @@ pub fn preflight(
         }
         num_iterations += 1;
     }
-    builder = builder.prepare_header::<TaikoHeaderPrepStrategy>()?;
+    builder = prepare_header(builder, network)?;
     let provider_db = builder.mut_db().unwrap();
 
     // Gather inclusion proofs for the initial and final state
+    let witness_time = Instant::now();
     let measurement = Measurement::start("Fetching storage proofs...", true);
     let (parent_proofs, proofs, num_storage_proofs) = provider_db.get_proofs()?;
     measurement.stop_with_count(&format!(
@@ -247,15 +474,174 @@ pub fn preflight(
         }
     }
     measurement.stop();
+    let witness_time = witness_time.elapsed();
 
     // Add the collected data to the input
-    Ok(GuestInput {
+    let input = GuestInput {
         parent_state_trie: state_trie,
         parent_storage: storage,
         contracts: contracts.into_iter().map(Bytes).collect(),
         ancestor_headers,
         ..input
-    })
+    };
+    Ok((input, witness_time))
+}
+
+/// Prepares the header with the rules of the chain the block is on.
+fn prepare_header(
+    builder: BlockBuilder<ProviderDb>,
+    network: Network,
+) -> Result<BlockBuilder<ProviderDb>> {
+    if network.is_taiko() {
+        TaikoHeaderPrepStrategy::prepare_header(builder)
+    } else {
+        EthHeaderPrepStrategy::prepare_header(builder)
+    }
+}
+
+/// Where the tx list of a proposal is stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TxListSource {
+    /// The tx list is passed in the `proposeBlock` calldata
+    Calldata,
+    /// The tx list is stored in one of the blobs attached to the propose tx, the blobs with
+    /// the given versioned hashes in order
+    Blob(Vec<B256>),
+}
+
+/// Detects where the tx list is stored using the proposal metadata and the blob hashes
+/// attached to the propose tx.
+fn get_tx_list_source(meta: &BlockMetadata, blob_hashes: &[B256]) -> Result<TxListSource> {
+    if !meta.blobUsed {
+        return Ok(TxListSource::Calldata);
+    }
+    // The metadata commits to the versioned hash of the blob that was used, all the blobs
+    // attached to the propose tx are fetched so the input holds the same blobs as the tx
+    if blob_hashes.contains(&meta.blobHash) {
+        return Ok(TxListSource::Blob(blob_hashes.to_vec()));
+    }
+    bail!(
+        "blob {} used by block {} is not attached to the propose tx",
+        meta.blobHash,
+        meta.id
+    );
+}
+
+/// Fetches the tx list of the proposal and verifies it against the proposal metadata.
+fn get_tx_list(
+    proposal_tx: &AlloyRpcTransaction,
+    proposal_event: &BlockProposed,
+    network: Network,
+    l1_inclusion_block_timestamp: u64,
+    beacon_rpc_url: Option<&str>,
+) -> Result<(Vec<u8>, Vec<B256>)> {
+    let blob_hashes = proposal_tx
+        .blob_versioned_hashes
+        .clone()
+        .unwrap_or_default();
+    match get_tx_list_source(&proposal_event.meta, &blob_hashes)? {
+        TxListSource::Blob(blob_hashes) => {
+            println!("tx list stored in {} blob(s)", blob_hashes.len());
+            let tx_list = get_blob_tx_list(
+                &blob_hashes,
+                network,
+                l1_inclusion_block_timestamp,
+                beacon_rpc_url,
+            )?;
+            Ok((tx_list, blob_hashes))
+        }
+        TxListSource::Calldata => {
+            println!("tx list stored in calldata");
+            // Get the tx list data directly from the propose transaction data
+            let proposal_call = proposeBlockCall::abi_decode(&proposal_tx.input, false)
+                .map_err(|e| anyhow!("failed to decode the propose tx calldata: {e}"))?;
+            let tx_list = proposal_call.txList.as_ref().to_owned();
+            // Without blobs the metadata commits to the hash of the tx list
+            let tx_list_hash = B256::from(keccak(&tx_list));
+            if tx_list_hash != proposal_event.meta.blobHash {
+                bail!(
+                    "tx list hash mismatch, expected: {}, got: {}",
+                    proposal_event.meta.blobHash,
+                    tx_list_hash
+                );
+            }
+            Ok((tx_list, Vec::new()))
+        }
+    }
+}
+
+/// Fetches the blobs with the given versioned hashes from the beacon node and concatenates
+/// them in the given order.
+fn get_blob_tx_list(
+    blob_hashes: &[B256],
+    network: Network,
+    l1_block_timestamp: u64,
+    beacon_rpc_url: Option<&str>,
+) -> Result<Vec<u8>> {
+    let beacon_rpc_url =
+        beacon_rpc_url.ok_or_else(|| anyhow!("beacon rpc required for blob data"))?;
+    let l2_chain_spec = get_network_spec(network);
+    // Get the blob data for this block
+    let slot_id = block_time_to_block_slot(
+        l1_block_timestamp,
+        l2_chain_spec.genesis_time,
+        l2_chain_spec.seconds_per_slot,
+    )?;
+    let blobs = get_blob_data(beacon_rpc_url, slot_id)?;
+    if blobs.data.is_empty() {
+        bail!("blob data not available anymore");
+    }
+    // Calculate the versioned hash of every sidecar from the blob itself so the data
+    // is verified
+    let sidecars = blobs
+        .data
+        .iter()
+        .map(|blob| (calc_blob_versioned_hash(&blob.blob), blob))
+        .collect::<Vec<_>>();
+    // Reassemble the tx list from the blobs in the committed order
+    let mut tx_list = Vec::with_capacity(blob_hashes.len() * BLOB_DATA_CAPACITY);
+    for blob_hash in blob_hashes.iter() {
+        let (_, tx_blob) = sidecars
+            .iter()
+            .find(|(versioned_hash, _)| blob_hash.0 == *versioned_hash)
+            .ok_or_else(|| anyhow!("no sidecar found for blob {blob_hash}"))?;
+        tx_list.extend(blob_to_bytes(&tx_blob.blob));
+    }
+    Ok(tx_list)
+}
+
+/// Verifies the tx list of a preconfirmed block against the metadata it will be proposed with.
+fn get_preconfirmed_tx_list(preconfirmation: &Preconfirmation) -> Result<(Vec<u8>, Vec<B256>)> {
+    let meta = &preconfirmation.meta;
+    let tx_list = preconfirmation.tx_list.to_vec();
+    if !meta.blobUsed {
+        let tx_list_hash = B256::from(keccak(&tx_list));
+        if tx_list_hash != meta.blobHash {
+            bail!(
+                "tx list hash mismatch, expected: {}, got: {}",
+                meta.blobHash,
+                tx_list_hash
+            );
+        }
+        return Ok((tx_list, Vec::new()));
+    }
+    // The blob isn't on the beacon chain yet, so get the versioned hash from the data
+    if tx_list.len() != BLOB_DATA_CAPACITY {
+        bail!(
+            "blob tx list has to be a single blob of {} bytes, got {}",
+            BLOB_DATA_CAPACITY,
+            tx_list.len()
+        );
+    }
+    let blob_hash = blob_versioned_hash(&tx_list)?;
+    if blob_hash != meta.blobHash {
+        bail!(
+            "blob hash mismatch, expected: {}, got: {}",
+            meta.blobHash,
+            blob_hash
+        );
+    }
+    Ok((tx_list, vec![blob_hash]))
 }
 
 // block_time_to_block_slot returns the slots of the given timestamp.
@@ -348,6 +734,17 @@ pub fn get_block(provider: &ReqwestProvider, block_number: u64, full: bool) -> R
     }
 }
 
+/// Fetches the latest block of the chain.
+fn get_head_block(provider: &ReqwestProvider) -> Result<AlloyBlock> {
+    let tokio_handle = tokio::runtime::Handle::current();
+    let response = tokio_handle.block_on(async {
+        provider
+            .get_block_by_number(BlockNumberOrTag::Latest, false)
+            .await
+    })?;
+    response.ok_or_else(|| anyhow!("No data for the latest block"))
+}
+
 fn get_block_proposed_event(
     provider: &ReqwestProvider,
     network: Network,
@@ -423,6 +820,44 @@ fn get_block_proposed_event(
     bail!("No BlockProposed event found for block {l2_block_number}");
 }
 
+fn get_batch_proposed_event(
+    provider: &ReqwestProvider,
+    network: Network,
+    block_hash: B256,
+    batch_id: u64,
+) -> Result<BatchProposed> {
+    let tokio_handle = tokio::runtime::Handle::current();
+
+    // Get the address that emited the event
+    let l1_address = get_network_spec(network).l1_contract.unwrap();
+
+    // Setup the filter to get the relevant events
+    let filter = Filter::new()
+        .address(l1_address)
+        .at_block_hash(block_hash)
+        .event_signature(BatchProposed::SIGNATURE_HASH);
+    // Now fetch the events
+    let logs = tokio_handle.block_on(async { provider.get_logs(&filter).await })?;
+
+    // Run over the logs returned to find the matching event for the specified batch
+    for log in logs {
+        let event = BatchProposed::decode_log(
+            &Log::new(
+                log.address(),
+                log.topics().to_vec(),
+                log.data().data.clone(),
+            )
+            .unwrap(),
+            false,
+        )
+        .unwrap();
+        if event.meta.batchId == batch_id {
+            return Ok(event.data);
+        }
+    }
+    bail!("No BatchProposed event found for batch {batch_id}");
+}
+
 fn get_transactions_from_block(block: &AlloyBlock) -> Vec<TxEnvelope> {
     let mut transactions: Vec<TxEnvelope> = Vec::new();
     if !block.transactions.is_empty() {
@@ -784,6 +1219,65 @@ mod test {
         .is_err());
     }
 
+    #[test]
+    fn test_tx_list_source() {
+        let blob_hash = B256::with_last_byte(1);
+        let mut meta = BlockMetadata {
+            blobHash: blob_hash,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_tx_list_source(&meta, &[blob_hash]).unwrap(),
+            TxListSource::Calldata
+        );
+        meta.blobUsed = true;
+        assert_eq!(
+            get_tx_list_source(&meta, &[blob_hash]).unwrap(),
+            TxListSource::Blob(vec![blob_hash])
+        );
+        // multiple blobs attached to the propose tx
+        assert_eq!(
+            get_tx_list_source(&meta, &[B256::ZERO, blob_hash]).unwrap(),
+            TxListSource::Blob(vec![B256::ZERO, blob_hash])
+        );
+        assert!(get_tx_list_source(&meta, &[]).is_err());
+        assert!(get_tx_list_source(&meta, &[B256::ZERO]).is_err());
+    }
+
+    #[test]
+    fn test_preconfirmed_tx_list() {
+        // calldata
+        let tx_list = Bytes::from(vec![1u8, 2, 3]);
+        let mut preconfirmation = Preconfirmation {
+            tx_list: tx_list.clone(),
+            meta: BlockMetadata {
+                blobHash: keccak(&tx_list).into(),
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            get_preconfirmed_tx_list(&preconfirmation).unwrap(),
+            (tx_list.to_vec(), Vec::new())
+        );
+        preconfirmation.meta.blobHash = B256::ZERO;
+        assert!(get_preconfirmed_tx_list(&preconfirmation).is_err());
+
+        // blobs
+        let zero_blob_hash =
+            b256!("010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014");
+        preconfirmation.meta.blobUsed = true;
+        preconfirmation.meta.blobHash = zero_blob_hash;
+        assert!(get_preconfirmed_tx_list(&preconfirmation).is_err());
+        preconfirmation.tx_list = vec![0u8; BLOB_DATA_CAPACITY].into();
+        assert_eq!(
+            get_preconfirmed_tx_list(&preconfirmation).unwrap().1,
+            vec![zero_blob_hash]
+        );
+        // The metadata commits to a single blob
+        preconfirmation.tx_list = vec![0u8; 2 * BLOB_DATA_CAPACITY].into();
+        assert!(get_preconfirmed_tx_list(&preconfirmation).is_err());
+    }
+
     #[ignore]
     #[test]
     fn json_to_ethers_blob_tx() {
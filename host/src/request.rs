@@ -1,8 +1,10 @@
 use core::fmt::Debug;
 use std::str::FromStr;
 
-use alloy_primitives::{Address, B256};
+use alloy_primitives::{Address, Bytes, B256};
+use alloy_rpc_types::Transaction;
 use anyhow::bail;
+use raiko_lib::input::BlockMetadata;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 
@@ -46,6 +48,32 @@ pub struct ProofRequest {
     /// the protocol instance data
     #[serde_as(as = "DisplayFromStr")]
     pub prover: Address,
+    /// the proposal data of a block that is not proposed yet
+    #[serde(default)]
+    pub preconfirmation: Option<Preconfirmation>,
+    /// prove all the blocks of this batch with a single proof instead of a single block
+    #[serde(default)]
+    pub batch_id: Option<u64>,
+    /// the L1 block the batch was proposed in, required when proving a batch
+    #[serde(default)]
+    pub l1_inclusion_block_number: Option<u64>,
+}
+
+/// A preconfirmed block that is not proposed on L1 yet, so the proposal data is taken from
+/// the request instead of from the proposal. The proof is the same as the proof of the
+/// proposed block, it commits to the metadata and so only holds if the block gets proposed
+/// with exactly this data. Being a preconfirmation is only reported in the response, it isn't
+/// part of the proof.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Preconfirmation {
+    /// the tx list as it will be proposed, the raw data of the single blob it is stored in
+    /// when `meta.blobUsed` is set
+    pub tx_list: Bytes,
+    /// the metadata the block will be proposed with
+    pub meta: BlockMetadata,
+    /// the anchor tx of the block, only needed when the block isn't on the L2 node yet
+    #[serde(default)]
+    pub anchor_tx: Option<Transaction>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
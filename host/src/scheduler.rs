@@ -0,0 +1,442 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tracing::info;
+
+use crate::{error::Result, merge};
+
+/// The job name used for fetching and building the input of a block.
+pub const PREFLIGHT_JOB: &str = "preflight";
+/// The job name used for the test run of the guest program on the host.
+pub const EXECUTION_JOB: &str = "execution";
+/// The job name used for proving with risc0 on Bonsai, only the upload of the input and the
+/// polling for the proof run on the machine.
+pub const RISC0_BONSAI_JOB: &str = "risc0_bonsai";
+
+/// The job name used for proving with the prover selected in the config.
+pub fn proving_job(config: &serde_json::Value) -> &str {
+    let proof_type = config["proof_type"].as_str().unwrap_or_default();
+    if proof_type == "risc0" && config["risc0"]["bonsai"].as_bool().unwrap_or_default() {
+        RISC0_BONSAI_JOB
+    } else {
+        proof_type
+    }
+}
+
+/// A bundle of machine resources, either the capacity of the machine or what a single job
+/// needs while it is running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Resources {
+    pub cores: u64,
+    pub ram_mb: u64,
+    pub gpus: u64,
+    /// SGX enclave page cache
+    pub epc_mb: u64,
+}
+
+impl Resources {
+    fn saturating_add(&self, other: &Resources) -> Resources {
+        Resources {
+            cores: self.cores.saturating_add(other.cores),
+            ram_mb: self.ram_mb.saturating_add(other.ram_mb),
+            gpus: self.gpus.saturating_add(other.gpus),
+            epc_mb: self.epc_mb.saturating_add(other.epc_mb),
+        }
+    }
+
+    fn saturating_sub(&self, other: &Resources) -> Resources {
+        Resources {
+            cores: self.cores.saturating_sub(other.cores),
+            ram_mb: self.ram_mb.saturating_sub(other.ram_mb),
+            gpus: self.gpus.saturating_sub(other.gpus),
+            epc_mb: self.epc_mb.saturating_sub(other.epc_mb),
+        }
+    }
+
+    fn fits_in(&self, capacity: &Resources) -> bool {
+        self.cores <= capacity.cores
+            && self.ram_mb <= capacity.ram_mb
+            && self.gpus <= capacity.gpus
+            && self.epc_mb <= capacity.epc_mb
+    }
+
+    /// Measures the resources of the machine the host is running on. Resources that can't
+    /// be measured are treated as unlimited.
+    pub fn measure() -> Resources {
+        let cores = std::thread::available_parallelism()
+            .map(|cores| cores.get() as u64)
+            .unwrap_or(u64::MAX);
+        let ram_mb = fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|meminfo| {
+                meminfo
+                    .lines()
+                    .find_map(|line| line.strip_prefix("MemTotal:"))
+                    .and_then(|kb| kb.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+            })
+            .map(|kb| kb / 1024)
+            .unwrap_or(u64::MAX);
+        let gpus = fs::read_dir("/dev")
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| {
+                        entry
+                            .file_name()
+                            .to_str()
+                            .and_then(|name| name.strip_prefix("nvidia"))
+                            .is_some_and(|id| {
+                                !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+                            })
+                    })
+                    .count() as u64
+            })
+            .unwrap_or_default();
+        // Only exposed by the kernel when SGX is available, unlimited otherwise so the
+        // direct mode isn't limited by it
+        let epc_mb = fs::read_dir("/sys/devices/system/node")
+            .ok()
+            .map(|nodes| {
+                nodes
+                    .filter_map(|node| node.ok())
+                    .filter_map(|node| {
+                        fs::read_to_string(node.path().join("x86").join("sgx_total_bytes")).ok()
+                    })
+                    .filter_map(|bytes| bytes.trim().parse::<u64>().ok())
+                    .sum::<u64>()
+                    / (1024 * 1024)
+            })
+            .filter(|epc_mb| *epc_mb > 0)
+            .unwrap_or(u64::MAX);
+        Resources {
+            cores,
+            ram_mb,
+            gpus,
+            epc_mb,
+        }
+    }
+}
+
+/// The resources every job needs, overridable per job in the config.
+fn default_profiles() -> HashMap<String, Resources> {
+    let profile = |cores, ram_mb, gpus, epc_mb| Resources {
+        cores,
+        ram_mb,
+        gpus,
+        epc_mb,
+    };
+    HashMap::from([
+        (PREFLIGHT_JOB.to_string(), profile(1, 2 * 1024, 0, 0)),
+        (EXECUTION_JOB.to_string(), profile(1, 2 * 1024, 0, 0)),
+        ("native".to_string(), profile(1, 1024, 0, 0)),
+        ("sgx".to_string(), profile(1, 2 * 1024, 0, 256)),
+        ("sp1".to_string(), profile(8, 16 * 1024, 0, 0)),
+        ("risc0".to_string(), profile(8, 16 * 1024, 0, 0)),
+        (RISC0_BONSAI_JOB.to_string(), profile(1, 1024, 0, 0)),
+    ])
+}
+
+/// Packs concurrent jobs against the capacity of the machine. A job only starts once the
+/// resources it needs are available, a job that needs more than the whole machine runs
+/// alone. Jobs are admitted in arrival order, so small jobs can't keep a big job waiting
+/// forever.
+#[derive(Debug)]
+pub struct Scheduler {
+    capacity: Resources,
+    profiles: HashMap<String, Resources>,
+    state: Mutex<State>,
+    released: Notify,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    used: Resources,
+    running: usize,
+    /// Tickets of the jobs waiting for resources, in arrival order
+    waiting: VecDeque<u64>,
+    next_ticket: u64,
+}
+
+impl State {
+    fn try_reserve(&mut self, resources: &Resources, capacity: &Resources) -> bool {
+        if self.running == 0 || self.used.saturating_add(resources).fits_in(capacity) {
+            self.used = self.used.saturating_add(resources);
+            self.running += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn new(capacity: Resources, profiles: HashMap<String, Resources>) -> Self {
+        Self {
+            capacity,
+            profiles,
+            state: Mutex::new(State::default()),
+            released: Notify::new(),
+        }
+    }
+
+    /// Builds the scheduler from the measured capacity and the default profiles, both
+    /// overridden by the `scheduler.capacity` and `scheduler.profiles` config entries.
+    pub fn from_config(config: &serde_json::Value) -> Result<Self> {
+        let config = &config["scheduler"];
+
+        let mut capacity = serde_json::to_value(Resources::measure())?;
+        if !config["capacity"].is_null() {
+            merge(&mut capacity, &config["capacity"]);
+        }
+        let capacity = Resources::deserialize(capacity)?;
+
+        let mut profiles = serde_json::to_value(default_profiles())?;
+        if !config["profiles"].is_null() {
+            merge(&mut profiles, &config["profiles"]);
+        }
+        let profiles = HashMap::<String, Resources>::deserialize(profiles)?;
+
+        info!("Scheduling jobs against {capacity:?}");
+        Ok(Self::new(capacity, profiles))
+    }
+
+    /// The resources needed by the given job, nothing for unknown jobs.
+    pub fn profile(&self, job: &str) -> Resources {
+        self.profiles.get(job).copied().unwrap_or_default()
+    }
+
+    /// Waits until the resources of the given job are available and reserves them until the
+    /// returned reservation is dropped. A job only starts once all the jobs that were waiting
+    /// before it have started.
+    pub async fn reserve(self: &Arc<Self>, job: &str) -> Reservation {
+        let resources = self.profile(job);
+        let reservation = || Reservation {
+            scheduler: self.clone(),
+            resources,
+        };
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            if state.waiting.is_empty() && state.try_reserve(&resources, &self.capacity) {
+                return reservation();
+            }
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiting.push_back(ticket);
+            Ticket {
+                scheduler: self,
+                ticket,
+            }
+        };
+        info!("Waiting for resources to run {job}");
+        loop {
+            // Register for the notification before checking so a release in between isn't missed
+            let released = self.released.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.waiting.front() == Some(&ticket.ticket)
+                    && state.try_reserve(&resources, &self.capacity)
+                {
+                    state.waiting.pop_front();
+                    drop(state);
+                    // The next job in line may fit as well
+                    self.released.notify_waiters();
+                    return reservation();
+                }
+            }
+            released.await;
+        }
+    }
+}
+
+/// The place of a waiting job in the queue, given up when the job stops waiting.
+struct Ticket<'a> {
+    scheduler: &'a Scheduler,
+    ticket: u64,
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        let was_first = {
+            let mut state = self.scheduler.state.lock().unwrap();
+            let position = state
+                .waiting
+                .iter()
+                .position(|ticket| *ticket == self.ticket);
+            if let Some(position) = position {
+                state.waiting.remove(position);
+            }
+            position == Some(0)
+        };
+        // Cancelled while first in line, let the next job check again
+        if was_first {
+            self.scheduler.released.notify_waiters();
+        }
+    }
+}
+
+/// Resources reserved for a running job, released on drop.
+#[derive(Debug)]
+pub struct Reservation {
+    scheduler: Arc<Scheduler>,
+    resources: Resources,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        {
+            let mut state = self.scheduler.state.lock().unwrap();
+            state.used = state.used.saturating_sub(&self.resources);
+            state.running -= 1;
+        }
+        self.scheduler.released.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn scheduler() -> Arc<Scheduler> {
+        let capacity = Resources {
+            cores: 8,
+            ram_mb: 16 * 1024,
+            gpus: 0,
+            epc_mb: 0,
+        };
+        Arc::new(Scheduler::new(capacity, default_profiles()))
+    }
+
+    #[tokio::test]
+    async fn test_jobs_packed_against_capacity() {
+        let scheduler = scheduler();
+        let _preflights = [
+            scheduler.reserve(PREFLIGHT_JOB).await,
+            scheduler.reserve(PREFLIGHT_JOB).await,
+        ];
+        // Not enough RAM left for a zkVM job next to the preflights
+        let risc0 = tokio::time::timeout(Duration::from_millis(50), scheduler.reserve("risc0"));
+        assert!(risc0.await.is_err());
+        // But still room for another small job
+        let _native = scheduler.reserve("native").await;
+    }
+
+    #[tokio::test]
+    async fn test_release_wakes_up_waiting_jobs() {
+        let scheduler = scheduler();
+        let preflight = scheduler.reserve(PREFLIGHT_JOB).await;
+        let waiting = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move {
+                scheduler.reserve("risc0").await;
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        drop(preflight);
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .expect("job should be scheduled once resources are released")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_jobs_admitted_in_order() {
+        let scheduler = scheduler();
+        let preflight = scheduler.reserve(PREFLIGHT_JOB).await;
+        let risc0 = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.reserve("risc0").await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // Would fit next to the preflight, but the risc0 job was waiting first
+        let native = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.reserve("native").await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!risc0.is_finished());
+        assert!(!native.is_finished());
+
+        drop(preflight);
+        let risc0 = tokio::time::timeout(Duration::from_secs(1), risc0)
+            .await
+            .expect("the first waiting job should be scheduled first")
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!native.is_finished());
+
+        drop(risc0);
+        tokio::time::timeout(Duration::from_secs(1), native)
+            .await
+            .expect("the next job should be scheduled once resources are released")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_job_leaves_the_queue() {
+        let scheduler = scheduler();
+        let _preflight = scheduler.reserve(PREFLIGHT_JOB).await;
+        let risc0 = tokio::time::timeout(Duration::from_millis(50), scheduler.reserve("risc0"));
+        assert!(risc0.await.is_err());
+        // The cancelled job doesn't block the jobs after it
+        let native = tokio::time::timeout(Duration::from_secs(1), scheduler.reserve("native"));
+        assert!(native.await.is_ok());
+        assert!(scheduler.state.lock().unwrap().waiting.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_job_runs_alone() {
+        // No EPC at all, the sgx job still has to run eventually
+        let scheduler = scheduler();
+        let sgx = scheduler.reserve("sgx").await;
+        let preflight =
+            tokio::time::timeout(Duration::from_millis(50), scheduler.reserve(PREFLIGHT_JOB));
+        assert!(preflight.await.is_err());
+        drop(sgx);
+        let _preflight = scheduler.reserve(PREFLIGHT_JOB).await;
+    }
+
+    #[tokio::test]
+    async fn test_bonsai_job_leaves_room_for_local_jobs() {
+        let scheduler = scheduler();
+        let config = serde_json::json!({ "proof_type": "risc0", "risc0": { "bonsai": true } });
+        assert_eq!(proving_job(&config), RISC0_BONSAI_JOB);
+        let _risc0 = scheduler.reserve(proving_job(&config)).await;
+        let preflight =
+            tokio::time::timeout(Duration::from_secs(1), scheduler.reserve(PREFLIGHT_JOB));
+        assert!(preflight.await.is_ok());
+
+        let config = serde_json::json!({ "proof_type": "risc0", "risc0": { "bonsai": false } });
+        assert_eq!(proving_job(&config), "risc0");
+        let config = serde_json::json!({ "proof_type": "sgx", "risc0": { "bonsai": true } });
+        assert_eq!(proving_job(&config), "sgx");
+    }
+
+    #[test]
+    fn test_profiles_from_config() {
+        let config = serde_json::json!({
+            "scheduler": {
+                "capacity": { "gpus": 2 },
+                "profiles": { "risc0": { "gpus": 1 } }
+            }
+        });
+        let scheduler = Scheduler::from_config(&config).unwrap();
+        assert_eq!(scheduler.capacity.gpus, 2);
+        assert_eq!(
+            scheduler.profile("risc0"),
+            Resources {
+                gpus: 1,
+                ..default_profiles()["risc0"]
+            }
+        );
+        assert_eq!(scheduler.profile("unknown"), Resources::default());
+    }
+}
@@ -2,14 +2,17 @@ use std::{
     fs::{self, File},
     path::PathBuf,
     str::FromStr,
+    sync::Arc,
 };
 
+use anyhow::anyhow;
 use hyper::{
     body::{Buf, HttpBody},
     header::HeaderValue,
     service::{make_service_fn, service_fn},
     Body, Method, Request, Response, Server, StatusCode,
 };
+use prometheus::{Encoder, TextEncoder};
 use raiko_lib::input::{get_input_path, GuestInput};
 use serde::Deserialize;
 use tower::ServiceBuilder;
@@ -17,9 +20,12 @@ use tracing::info;
 
 use crate::{
     error::HostError,
-    execution::execute,
+    execution::{execute, execute_batch},
     get_config,
+    policy::SamplingPolicy,
     request::{JsonRpcError, JsonRpcRequest, JsonRpcResponse, JsonRpcResponseError, *},
+    scheduler::Scheduler,
+    version::VersionInfo,
     Opt,
 };
 
@@ -34,10 +40,12 @@ pub fn serve(opt: Opt) -> tokio::task::JoinHandle<()> {
         .expect("valid socket address");
 
     tokio::spawn(async move {
+        let config = get_config(None).expect("valid config");
+        let scheduler = Arc::new(Scheduler::from_config(&config).expect("valid scheduler config"));
         let handler = if let Some(cache) = opt.cache_path {
-            Handler::new_with_cache(cache)
+            Handler::new_with_cache(cache, scheduler)
         } else {
-            Handler::new()
+            Handler::new(scheduler)
         };
 
         let service = service_fn(move |req| {
@@ -81,20 +89,25 @@ fn set_headers(headers: &mut hyper::HeaderMap, extended: bool) {
 #[derive(Clone)]
 struct Handler {
     cache_dir: Option<PathBuf>,
+    scheduler: Arc<Scheduler>,
 }
 
 impl Handler {
-    pub fn new() -> Self {
-        Self { cache_dir: None }
+    pub fn new(scheduler: Arc<Scheduler>) -> Self {
+        Self {
+            cache_dir: None,
+            scheduler,
+        }
     }
 
-    pub fn new_with_cache(dir: PathBuf) -> Self {
+    pub fn new_with_cache(dir: PathBuf, scheduler: Arc<Scheduler>) -> Self {
         if !dir.exists() {
             fs::create_dir_all(&dir)
                 .unwrap_or_else(|_| panic!("Failed to create cache directory {:?}", dir));
         }
         Self {
             cache_dir: Some(dir),
+            scheduler,
         }
     }
 
@@ -150,10 +163,7 @@ impl Handler {
 
             // json-rpc
             (&Method::POST, "/") => {
-                let body_bytes = hyper::body::aggregate(req.into_body())
-                    .await
-                    .unwrap()
-                    .reader();
+                let body_bytes = hyper::body::aggregate(req.into_body()).await?.reader();
                 let json_req: Result<JsonRpcRequest<Vec<serde_json::Value>>, serde_json::Error> =
                     serde_json::from_reader(body_bytes);
 
@@ -208,19 +218,27 @@ impl Handler {
                 Ok(resp)
             }
 
-            // TODO(Cecilia): better way to serve metrics, perhaps can be done in OpenAPI
-            // refactoring serve metrics
-            // (&Method::GET, "/metrics") => {
-            //     let encoder = TextEncoder::new();
-            //     let mut buffer = vec![];
-            //     let mf = prometheus::gather();
-            //     encoder.encode(&mf, &mut buffer).unwrap();
-            //     let resp = Response::builder()
-            //         .header(hyper::header::CONTENT_TYPE, encoder.format_type())
-            //         .body(Body::from(buffer))
-            //         .unwrap();
-            //     Ok(resp)
-            // }
+            // serve the prometheus metrics
+            (&Method::GET, "/metrics") => {
+                let encoder = TextEncoder::new();
+                let mut buffer = vec![];
+                let mf = prometheus::gather();
+                encoder.encode(&mf, &mut buffer).unwrap();
+                let resp = Response::builder()
+                    .header(hyper::header::CONTENT_TYPE, encoder.format_type())
+                    .body(Body::from(buffer))
+                    .unwrap();
+                Ok(resp)
+            }
+
+            // serve the version and provenance of this host
+            (&Method::GET, "/version") => {
+                let mut resp = Response::new(Body::from(
+                    serde_json::to_vec(&VersionInfo::current()).unwrap(),
+                ));
+                set_headers(resp.headers_mut(), false);
+                Ok(resp)
+            }
 
             // everything else
             _ => {
@@ -240,40 +258,135 @@ impl Handler {
             // Generate a proof for a block
             "proof" => {
                 // Get the request data sent through json-rpc
-                let request: serde_json::Value =
-                    params.first().expect("params must not be empty").to_owned();
+                let request: serde_json::Value = params
+                    .first()
+                    .ok_or_else(|| anyhow!("params must not be empty"))?
+                    .to_owned();
 
                 // Use it to find cached input if any  build the config
-                let config = get_config(Some(request)).unwrap();
-                let req = ProofRequest::deserialize(config.clone()).unwrap();
+                let config = get_config(Some(request))?;
+                let req = ProofRequest::deserialize(config.clone())?;
+                let proof_type = ProofType::from_str(
+                    config["proof_type"]
+                        .as_str()
+                        .ok_or_else(|| anyhow!("proof_type missing"))?,
+                )?;
+                // A batch is proven from its L1 proposal, preconfirmations are for single blocks
+                if req.batch_id.is_some() && req.preconfirmation.is_some() {
+                    return Err(HostError::Anyhow(anyhow!(
+                        "a request can't have both a batch_id and a preconfirmation"
+                    )));
+                }
+                // A batch is always proven as a whole, so it isn't sampled or cached
+                if let Some(batch_id) = req.batch_id {
+                    println!("# Generating proof for batch {batch_id} on {}", req.network);
+                    return match proof_type {
+                        ProofType::Native => {
+                            execute_batch::<super::execution::NativeDriver>(
+                                &config,
+                                &self.scheduler,
+                            )
+                            .await
+                        }
+                        // Fail before fetching the whole batch
+                        ProofType::Sp1 | ProofType::Risc0 => Err(HostError::GuestError(format!(
+                            "{proof_type:?} guests don't take batch inputs yet, batch {batch_id} \
+                             can only be proven with the native or SGX prover"
+                        ))),
+                        #[cfg(feature = "sgx")]
+                        ProofType::Sgx => {
+                            execute_batch::<sgx_prover::SgxProver>(&config, &self.scheduler).await
+                        }
+                        _ => Err(HostError::Anyhow(anyhow!(
+                            "Prover {proof_type:?} not enabled!"
+                        ))),
+                    };
+                }
                 println!(
                     "# Generating proof for block {} on {}",
                     req.block_number, req.network
                 );
-                let cached_input = self.get(req.block_number, &req.network);
+
+                // Preconfirmed blocks can still be proposed differently so they aren't cached
+                let is_preconfirmation = req.preconfirmation.is_some();
+                let cached_input = if is_preconfirmation {
+                    None
+                } else {
+                    self.get(req.block_number, &req.network)
+                };
+
+                // Skip the blocks that are not part of the sampled subset, preconfirmed blocks
+                // may not have a hash to sample on yet so they are always proven
+                let policy = if is_preconfirmation {
+                    None
+                } else {
+                    SamplingPolicy::from_config(&config)?
+                };
+                let ballot = match policy {
+                    Some(policy) => {
+                        // A cached input already has the block hash, so only new blocks need
+                        // the RPC
+                        let ballot = match &cached_input {
+                            Some(input) => policy.ballot(input.block_hash, req.prover),
+                            None => policy.cast_ballot(&req).await?,
+                        };
+                        if !ballot.selected {
+                            println!("# Block {} not selected for proving", req.block_number);
+                            return Ok(serde_json::json!({ "sampling": ballot }));
+                        }
+                        Some(ballot)
+                    }
+                    None => None,
+                };
 
                 // Run the selected prover
-                let proof_type =
-                    ProofType::from_str(config["proof_type"].as_str().unwrap()).unwrap();
-                let (input, proof) = match proof_type {
+                let (input, mut proof) = match proof_type {
                     ProofType::Native => {
-                        execute::<super::execution::NativeDriver>(&config, cached_input).await
+                        execute::<super::execution::NativeDriver>(
+                            &config,
+                            cached_input,
+                            &self.scheduler,
+                        )
+                        .await
                     }
                     #[cfg(feature = "sp1")]
-                    ProofType::Sp1 => execute::<sp1_prover::Sp1Prover>(&config, cached_input).await,
+                    ProofType::Sp1 => {
+                        execute::<sp1_prover::Sp1Prover>(&config, cached_input, &self.scheduler)
+                            .await
+                    }
                     #[cfg(feature = "risc0")]
                     ProofType::Risc0 => {
-                        execute::<risc0_prover::Risc0Prover>(&config, cached_input).await
+                        execute::<risc0_prover::Risc0Prover>(&config, cached_input, &self.scheduler)
+                            .await
                     }
                     #[cfg(feature = "sgx")]
-                    ProofType::Sgx => execute::<sgx_prover::SgxProver>(&config, cached_input).await,
-                    _ => unimplemented!("Prover {:?} not enabled!", proof_type),
+                    ProofType::Sgx => {
+                        execute::<sgx_prover::SgxProver>(&config, cached_input, &self.scheduler)
+                            .await
+                    }
+                    _ => Err(HostError::Anyhow(anyhow!(
+                        "Prover {proof_type:?} not enabled!"
+                    ))),
                 }?;
                 // Cache the input
-                self.set(req.block_number, &req.network, input)?;
+                if !is_preconfirmation {
+                    self.set(req.block_number, &req.network, input)?;
+                }
+                // Record the sampling decision so it can be checked later
+                if let (Some(ballot), Some(proof)) = (ballot, proof.as_object_mut()) {
+                    proof.insert("sampling".to_string(), serde_json::to_value(ballot)?);
+                }
+                // Only valid as long as the block gets proposed with the same data. The marker
+                // only describes the response, the proof itself is the same as the proof of
+                // the proposed block
+                if is_preconfirmation {
+                    if let Some(proof) = proof.as_object_mut() {
+                        proof.insert("preconfirmation".to_string(), serde_json::Value::Bool(true));
+                    }
+                }
                 Ok(proof)
             }
-            _ => todo!(),
+            _ => Err(HostError::Anyhow(anyhow!("method {method} not supported"))),
         }
     }
 }
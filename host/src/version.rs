@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "risc0")]
+use alloy_primitives::hex;
+use raiko_lib::consts::{get_network_spec, Network};
+use raiko_primitives::{keccak::keccak, B256};
+use serde::{Deserialize, Serialize};
+
+/// What exactly this host is running, served on `GET /version`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub version: String,
+    pub git_commit: String,
+    /// Unix timestamp of the build
+    pub build_timestamp: u64,
+    /// The provers compiled into this host
+    pub features: Vec<String>,
+    /// The guest programs the provers run, by prover
+    pub guests: BTreeMap<String, GuestInfo>,
+    /// keccak of the JSON encoded chain spec, by network
+    pub chain_specs: BTreeMap<String, B256>,
+}
+
+/// What the proofs of a guest are checked against. That is the image id for risc0, the
+/// keccak of the ELF for sp1 (the SDK verifies its proofs against the ELF itself, it doesn't
+/// have a separate verification key) and the MRENCLAVE for sgx.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GuestInfo {
+    /// `image_id`, `elf_keccak` or `mr_enclave`
+    pub id_type: String,
+    pub id: String,
+}
+
+impl VersionInfo {
+    pub fn current() -> Self {
+        let features = [
+            ("sp1", cfg!(feature = "sp1")),
+            ("risc0", cfg!(feature = "risc0")),
+            ("sgx", cfg!(feature = "sgx")),
+        ]
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(feature, _)| feature.to_string())
+        .collect();
+
+        #[allow(unused_mut)]
+        let mut guests = BTreeMap::new();
+        #[cfg(feature = "sp1")]
+        guests.insert(
+            "sp1".to_string(),
+            GuestInfo {
+                id_type: "elf_keccak".to_string(),
+                id: B256::from(keccak(sp1_prover::ELF)).to_string(),
+            },
+        );
+        #[cfg(feature = "risc0")]
+        guests.insert(
+            "risc0".to_string(),
+            GuestInfo {
+                id_type: "image_id".to_string(),
+                id: format!(
+                    "0x{}",
+                    hex::encode(bytemuck::cast_slice::<u32, u8>(
+                        &risc0_prover::RISC0_METHODS_ID
+                    ))
+                ),
+            },
+        );
+        // Only known once the guest has been signed by the setup
+        #[cfg(feature = "sgx")]
+        if let Some(mr_enclave) = sgx_prover::mr_enclave() {
+            guests.insert(
+                "sgx".to_string(),
+                GuestInfo {
+                    id_type: "mr_enclave".to_string(),
+                    id: mr_enclave.to_string(),
+                },
+            );
+        }
+
+        let chain_specs = [
+            Network::Ethereum,
+            Network::Holesky,
+            Network::TaikoA6,
+            Network::TaikoA7,
+        ]
+        .into_iter()
+        .map(|network| {
+            let spec = serde_json::to_vec(&get_network_spec(network))
+                .expect("chain spec serializes to json");
+            (network.to_string(), B256::from(keccak(spec)))
+        })
+        .collect();
+
+        VersionInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: env!("RAIKO_GIT_COMMIT").to_string(),
+            build_timestamp: env!("RAIKO_BUILD_TIMESTAMP")
+                .parse()
+                .expect("build timestamp is a number"),
+            features,
+            guests,
+            chain_specs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_spec_hashes_are_stable() {
+        let info = VersionInfo::current();
+        assert_eq!(info.chain_specs.len(), 4);
+        assert_eq!(
+            info.chain_specs,
+            VersionInfo::current().chain_specs,
+            "chain spec hashes have to be deterministic to be comparable between hosts"
+        );
+        assert_ne!(info.chain_specs["taiko_a7"], info.chain_specs["ethereum"]);
+    }
+}
@@ -38,7 +38,7 @@ use crate::{
     clear_line,
     consts::{get_network_spec, GWEI_TO_WEI},
     guest_mem_forget, inplace_print, print_duration,
-    taiko_utils::{check_anchor_tx, generate_transactions},
+    taiko_utils::{block_tx_list, check_anchor_tx, generate_transactions},
     time::{AddAssign, Duration, Instant},
     Measurement,
 };
@@ -75,18 +75,23 @@ impl TxExecStrategy for TkoTxExecStrategy {
         println!("spec_id: {:?}", spec_id);
 
         let network = block_builder.input.network;
-        let is_taiko = network.is_taiko();
+        let is_taiko = block_builder.chain_spec.is_taiko;
 
         // generate the transactions from the tx list
         // For taiko blocks, insert the anchor tx as the first transaction
-        let anchor_tx = if block_builder.input.network.is_taiko() {
+        let anchor_tx = if is_taiko {
             Some(serde_json::from_str(&block_builder.input.taiko.anchor_tx.clone()).unwrap())
         } else {
             None
         };
+        let taiko = &block_builder.input.taiko;
         let mut transactions = generate_transactions(
-            block_builder.input.taiko.block_proposed.meta.blobUsed,
-            &block_builder.input.taiko.tx_list,
+            taiko.block_proposed.meta.blobUsed,
+            block_tx_list(
+                &taiko.block_proposed.meta,
+                &taiko.tx_list,
+                &taiko.tx_blob_hashes,
+            ),
             anchor_tx,
         );
 
@@ -172,7 +177,7 @@ impl TxExecStrategy for TkoTxExecStrategy {
             let tx_env = &mut evm.env_mut().tx;
             fill_eth_tx_env(tx_env, &tx)?;
             // Set and check some taiko specific values
-            if network.is_taiko() {
+            if is_taiko {
                 // set if the tx is the anchor tx
                 tx_env.taiko.is_anchor = is_anchor;
                 // set the treasury address
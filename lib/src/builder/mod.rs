@@ -13,20 +13,23 @@
 // limitations under the License.
 
 use alloy_consensus::Header as AlloyConsensusHeader;
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use raiko_primitives::mpt::MptNode;
 use revm::{Database, DatabaseCommit};
 
 pub use self::execute::TkoTxExecStrategy;
+#[cfg(not(feature = "std"))]
+use crate::no_std::*;
 use crate::{
     builder::{
         finalize::{BlockFinalizeStrategy, MemDbBlockFinalizeStrategy},
         initialize::{DbInitStrategy, MemDbInitStrategy},
-        prepare::{HeaderPrepStrategy, TaikoHeaderPrepStrategy},
+        prepare::{EthHeaderPrepStrategy, HeaderPrepStrategy, TaikoHeaderPrepStrategy},
     },
     consts::{get_network_spec, ChainSpec},
-    input::GuestInput,
+    input::{GuestBatchInput, GuestInput},
     mem_db::MemDb,
+    taiko_utils::HeaderHasher,
 };
 
 pub mod execute;
@@ -129,6 +132,45 @@ impl BlockBuilderStrategy for TaikoStrategy {
     type TxExecStrategy = TkoTxExecStrategy;
     type BlockFinalizeStrategy = MemDbBlockFinalizeStrategy;
 }
+/// The [BlockBuilderStrategy] for building a vanilla Ethereum block.
+pub struct EthereumStrategy {}
+impl BlockBuilderStrategy for EthereumStrategy {
+    type DbInitStrategy = MemDbInitStrategy;
+    type HeaderPrepStrategy = EthHeaderPrepStrategy;
+    type TxExecStrategy = TkoTxExecStrategy;
+    type BlockFinalizeStrategy = MemDbBlockFinalizeStrategy;
+}
+
+/// Builds a block from the given input using the strategy selected by the chain spec.
+pub fn build_block(input: &GuestInput) -> Result<(AlloyConsensusHeader, MptNode)> {
+    if get_network_spec(input.network).is_taiko {
+        TaikoStrategy::build_from(input)
+    } else {
+        EthereumStrategy::build_from(input)
+    }
+}
+
+/// Builds all the blocks of a batch in order, returning the header of every block.
+pub fn build_batch(input: &GuestBatchInput) -> Result<Vec<AlloyConsensusHeader>> {
+    ensure!(!input.inputs.is_empty(), "batch without blocks");
+    let mut headers: Vec<AlloyConsensusHeader> = Vec::with_capacity(input.inputs.len());
+    for block_input in &input.inputs {
+        // The parent state is checked against the parent header, so linking the headers
+        // also links the state of the blocks
+        if let Some(parent) = headers.last() {
+            ensure!(
+                block_input.parent_header.hash() == parent.hash(),
+                "block {} does not build on block {} of the batch",
+                block_input.block_number,
+                parent.number
+            );
+        }
+        let (header, _mpt_node) = build_block(block_input)?;
+        headers.push(header);
+    }
+    Ok(headers)
+}
+
 pub trait TxExecStrategy {
     fn execute_transactions<D>(block_builder: BlockBuilder<D>) -> Result<BlockBuilder<D>>
     where
@@ -16,9 +16,15 @@ use core::fmt::Debug;
 
 use alloy_consensus::Header as AlloyConsensusHeader;
 use anyhow::{bail, Context, Result};
+use raiko_primitives::U256;
 use revm::{Database, DatabaseCommit};
 
-use crate::{builder::BlockBuilder, consts::MAX_EXTRA_DATA_BYTES, taiko_utils::HeaderHasher};
+use crate::{
+    builder::BlockBuilder,
+    consts::{Eip1559Constants, GAS_LIMIT_BOUND_DIVISOR, MAX_EXTRA_DATA_BYTES, MIN_GAS_LIMIT},
+    input::GuestInput,
+    taiko_utils::HeaderHasher,
+};
 
 pub trait HeaderPrepStrategy {
     fn prepare_header<D>(block_builder: BlockBuilder<D>) -> Result<BlockBuilder<D>>
@@ -45,35 +51,158 @@ impl HeaderPrepStrategy for TaikoHeaderPrepStrategy {
             );
         }
         // Validate extra data
-        let extra_data_bytes = block_builder.input.extra_data.len();
-        if extra_data_bytes > MAX_EXTRA_DATA_BYTES {
+        validate_extra_data(&block_builder.input.extra_data)?;
+        // Derive header
+        block_builder.header = Some(derive_header(&block_builder.input)?);
+        Ok(block_builder)
+    }
+}
+
+/// Header preparation for vanilla Ethereum blocks, enforcing the standard fee rules.
+pub struct EthHeaderPrepStrategy {}
+
+impl HeaderPrepStrategy for EthHeaderPrepStrategy {
+    fn prepare_header<D>(mut block_builder: BlockBuilder<D>) -> Result<BlockBuilder<D>>
+    where
+        D: Database + DatabaseCommit,
+        <D as Database>::Error: Debug,
+    {
+        let parent_header = &block_builder.input.parent_header;
+        // Validate gas limit
+        let diff = parent_header
+            .gas_limit
+            .abs_diff(block_builder.input.gas_limit);
+        let limit = parent_header.gas_limit / GAS_LIMIT_BOUND_DIVISOR;
+        if diff >= limit || block_builder.input.gas_limit < MIN_GAS_LIMIT {
+            bail!(
+                "Invalid gas limit: expected {} +- {}, got {}",
+                parent_header.gas_limit,
+                limit,
+                block_builder.input.gas_limit,
+            );
+        }
+        // Validate timestamp
+        if block_builder.input.timestamp <= parent_header.timestamp {
             bail!(
-                "Invalid extra data: expected <= {}, got {}",
-                MAX_EXTRA_DATA_BYTES,
-                extra_data_bytes,
-            )
+                "Invalid timestamp: expected > {}, got {}",
+                parent_header.timestamp,
+                block_builder.input.timestamp,
+            );
+        }
+        // Validate extra data
+        validate_extra_data(&block_builder.input.extra_data)?;
+        // Validate base fee
+        let base_fee = calc_base_fee(parent_header, block_builder.chain_spec.gas_constants())?;
+        if base_fee != U256::from(block_builder.input.base_fee_per_gas) {
+            bail!(
+                "Invalid base fee: expected {}, got {}",
+                base_fee,
+                block_builder.input.base_fee_per_gas,
+            );
         }
         // Derive header
-        let number: u64 = block_builder.input.parent_header.number;
-        block_builder.header = Some(AlloyConsensusHeader {
-            // Initialize fields that we can compute from the parent
-            parent_hash: block_builder.input.parent_header.hash(),
-            number: number
-                .checked_add(1)
-                .with_context(|| "Invalid block number: too large")?,
-            base_fee_per_gas: Some(block_builder.input.base_fee_per_gas),
-            // Initialize metadata from input
-            beneficiary: block_builder.input.beneficiary,
-            gas_limit: block_builder.input.gas_limit,
-            timestamp: block_builder.input.timestamp,
-            mix_hash: block_builder.input.mix_hash,
-            extra_data: block_builder.input.extra_data.clone(),
-            blob_gas_used: block_builder.input.blob_gas_used,
-            excess_blob_gas: block_builder.input.excess_blob_gas,
-            parent_beacon_block_root: block_builder.input.parent_beacon_block_root,
-            // do not fill the remaining fields
-            ..Default::default()
-        });
+        block_builder.header = Some(derive_header(&block_builder.input)?);
         Ok(block_builder)
     }
 }
+
+fn validate_extra_data(extra_data: &[u8]) -> Result<()> {
+    let extra_data_bytes = extra_data.len();
+    if extra_data_bytes > MAX_EXTRA_DATA_BYTES {
+        bail!(
+            "Invalid extra data: expected <= {}, got {}",
+            MAX_EXTRA_DATA_BYTES,
+            extra_data_bytes,
+        )
+    }
+    Ok(())
+}
+
+fn derive_header(input: &GuestInput) -> Result<AlloyConsensusHeader> {
+    let number: u64 = input.parent_header.number;
+    Ok(AlloyConsensusHeader {
+        // Initialize fields that we can compute from the parent
+        parent_hash: input.parent_header.hash(),
+        number: number
+            .checked_add(1)
+            .with_context(|| "Invalid block number: too large")?,
+        base_fee_per_gas: Some(input.base_fee_per_gas),
+        // Initialize metadata from input
+        beneficiary: input.beneficiary,
+        gas_limit: input.gas_limit,
+        timestamp: input.timestamp,
+        mix_hash: input.mix_hash,
+        extra_data: input.extra_data.clone(),
+        blob_gas_used: input.blob_gas_used,
+        excess_blob_gas: input.excess_blob_gas,
+        parent_beacon_block_root: input.parent_beacon_block_root,
+        // do not fill the remaining fields
+        ..Default::default()
+    })
+}
+
+/// Computes the base fee of the child of `parent` following
+/// [EIP-1559](https://eips.ethereum.org/EIPS/eip-1559).
+pub fn calc_base_fee(
+    parent: &AlloyConsensusHeader,
+    eip_1559_constants: &Eip1559Constants,
+) -> Result<U256> {
+    let parent_base_fee = U256::from(
+        parent
+            .base_fee_per_gas
+            .with_context(|| "Parent header has no base fee")?,
+    );
+    let parent_gas_used = U256::from(parent.gas_used);
+    let parent_gas_target = U256::from(parent.gas_limit) / eip_1559_constants.elasticity_multiplier;
+
+    if parent_gas_used == parent_gas_target {
+        Ok(parent_base_fee)
+    } else if parent_gas_used > parent_gas_target {
+        let gas_used_delta = parent_gas_used - parent_gas_target;
+        let base_fee_delta = (parent_base_fee * gas_used_delta
+            / parent_gas_target
+            / eip_1559_constants.base_fee_max_increase_denominator)
+            .max(U256::from(1));
+        Ok(parent_base_fee + base_fee_delta)
+    } else {
+        let gas_used_delta = parent_gas_target - parent_gas_used;
+        let base_fee_delta = parent_base_fee * gas_used_delta
+            / parent_gas_target
+            / eip_1559_constants.base_fee_max_decrease_denominator;
+        Ok(parent_base_fee.saturating_sub(base_fee_delta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parent(gas_used: u64) -> AlloyConsensusHeader {
+        AlloyConsensusHeader {
+            gas_limit: 30_000_000,
+            gas_used,
+            base_fee_per_gas: Some(1_000_000_000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_calc_base_fee() {
+        let constants = Eip1559Constants::default();
+        // at target
+        assert_eq!(
+            calc_base_fee(&parent(15_000_000), &constants).unwrap(),
+            U256::from(1_000_000_000u64)
+        );
+        // full block: +12.5%
+        assert_eq!(
+            calc_base_fee(&parent(30_000_000), &constants).unwrap(),
+            U256::from(1_125_000_000u64)
+        );
+        // empty block: -12.5%
+        assert_eq!(
+            calc_base_fee(&parent(0), &constants).unwrap(),
+            U256::from(875_000_000u64)
+        );
+    }
+}
@@ -34,6 +34,12 @@ pub const ONE: U256 = uint!(1_U256);
 /// Maximum size of extra data.
 pub const MAX_EXTRA_DATA_BYTES: usize = 32;
 
+/// Minimum gas limit of an Ethereum block.
+pub const MIN_GAS_LIMIT: u64 = 5000;
+
+/// Maximum change of the gas limit compared to the parent block (1/1024).
+pub const GAS_LIMIT_BOUND_DIVISOR: u64 = 1024;
+
 /// Maximum allowed block number difference for the `block_hash` call.
 pub const MAX_BLOCK_HASH_AGE: u64 = 256;
 
@@ -61,8 +67,12 @@ pub const ETH_MAINNET_CHAIN_SPEC: Lazy<ChainSpec> = Lazy::new(|| {
         l1_contract: None,
         l2_contract: None,
         sgx_verifier_address: None,
+        risc0_verifier_address: None,
+        sp1_verifier_address: None,
         genesis_time: 0u64,
         seconds_per_slot: 1u64,
+        is_taiko: false,
+        batch_fork: ForkCondition::TBD,
     }
 });
 
@@ -86,8 +96,12 @@ pub const ETH_HOLESKY_CHAIN_SPEC: Lazy<ChainSpec> = Lazy::new(|| {
         l1_contract: None,
         l2_contract: None,
         sgx_verifier_address: None,
+        risc0_verifier_address: None,
+        sp1_verifier_address: None,
         genesis_time: 0u64,
         seconds_per_slot: 1u64,
+        is_taiko: false,
+        batch_fork: ForkCondition::TBD,
     }
 });
 
@@ -110,8 +124,12 @@ pub const TAIKO_A6_CHAIN_SPEC: Lazy<ChainSpec> = Lazy::new(|| ChainSpec {
     sgx_verifier_address: Some(
         Address::from_str("0x558E38a3286916934Cb63ced04558A52F7Ce67a9").unwrap(),
     ),
+    risc0_verifier_address: None,
+    sp1_verifier_address: None,
     genesis_time: 0u64,
     seconds_per_slot: 1u64,
+    is_taiko: true,
+    batch_fork: ForkCondition::TBD,
 });
 
 /// The Taiko A7 specification.
@@ -133,8 +151,13 @@ pub const TAIKO_A7_CHAIN_SPEC: Lazy<ChainSpec> = Lazy::new(|| ChainSpec {
     sgx_verifier_address: Some(
         Address::from_str("0x532EFBf6D62720D0B2a2Bb9d11066E8588cAE6D9").unwrap(),
     ),
+    risc0_verifier_address: None,
+    sp1_verifier_address: None,
     genesis_time: 1695902400u64,
     seconds_per_slot: 12u64,
+    is_taiko: true,
+    // Pacaya
+    batch_fork: ForkCondition::Block(1299888),
 });
 
 pub fn get_network_spec(network: Network) -> ChainSpec {
@@ -147,13 +170,14 @@ pub fn get_network_spec(network: Network) -> ChainSpec {
 }
 
 /// The condition at which a fork is activated.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub enum ForkCondition {
     /// The fork is activated with a certain block.
     Block(BlockNumber),
     /// The fork is activated with a specific timestamp.
     Timestamp(u64),
     /// The fork is not yet active.
+    #[default]
     TBD,
 }
 
@@ -199,8 +223,16 @@ pub struct ChainSpec {
     pub l1_contract: Option<Address>,
     pub l2_contract: Option<Address>,
     pub sgx_verifier_address: Option<Address>,
+    /// The verifiers of the zk proofs, only committed to by the batch proofs
+    pub risc0_verifier_address: Option<Address>,
+    pub sp1_verifier_address: Option<Address>,
     pub genesis_time: u64,
     pub seconds_per_slot: u64,
+    /// Taiko L2 blocks (anchor tx, protocol fee rules) or vanilla Ethereum blocks
+    pub is_taiko: bool,
+    /// From when the L2 blocks are proposed in batches (`BatchProposed` and the `anchorV3`
+    /// anchor tx), the Pacaya fork, `TBD` on the networks that don't use the batch protocol
+    pub batch_fork: ForkCondition,
 }
 
 impl ChainSpec {
@@ -218,8 +250,12 @@ impl ChainSpec {
             l1_contract: None,
             l2_contract: None,
             sgx_verifier_address: None,
+            risc0_verifier_address: None,
+            sp1_verifier_address: None,
             genesis_time: 0u64,
             seconds_per_slot: 1u64,
+            is_taiko: false,
+            batch_fork: ForkCondition::TBD,
         }
     }
     /// Returns the network chain ID.
@@ -244,6 +280,14 @@ impl ChainSpec {
     pub fn gas_constants(&self) -> &Eip1559Constants {
         &self.eip_1559_constants
     }
+    /// Returns whether the blocks of the network can be proposed in batches.
+    pub fn has_batch_protocol(&self) -> bool {
+        !matches!(self.batch_fork, ForkCondition::TBD)
+    }
+    /// Returns whether the given block is proposed as part of a batch.
+    pub fn is_batch_block(&self, block_no: BlockNumber, timestamp: u64) -> bool {
+        self.batch_fork.active(block_no, timestamp)
+    }
 
     fn spec_id(&self, block_no: BlockNumber, timestamp: u64) -> Option<SpecId> {
         for (spec_id, fork) in self.hard_forks.iter().rev() {
@@ -296,12 +340,7 @@ impl ToString for Network {
 
 impl Network {
     pub fn is_taiko(&self) -> bool {
-        match self {
-            Network::Ethereum => false,
-            Network::Holesky => false,
-            Network::TaikoA6 => true,
-            Network::TaikoA7 => true,
-        }
+        get_network_spec(*self).is_taiko
     }
 }
 
@@ -325,4 +364,24 @@ mod tests {
             Some(SpecId::SHANGHAI)
         );
     }
+
+    #[test]
+    fn network_block_type() {
+        assert!(!Network::Ethereum.is_taiko());
+        assert!(!Network::Holesky.is_taiko());
+        assert!(Network::TaikoA6.is_taiko());
+        assert!(Network::TaikoA7.is_taiko());
+    }
+
+    #[test]
+    fn batch_protocol() {
+        // Only A7 is past the Pacaya fork
+        for network in [Network::Ethereum, Network::Holesky, Network::TaikoA6] {
+            assert!(!get_network_spec(network).has_batch_protocol());
+        }
+        let spec = get_network_spec(Network::TaikoA7);
+        assert!(spec.has_batch_protocol());
+        assert!(!spec.is_batch_block(1299887, 0));
+        assert!(spec.is_batch_block(1299888, 0));
+    }
 }
@@ -86,11 +86,35 @@ pub struct TaikoGuestInput {
     /// header
     #[serde_as(as = "RlpBytes")]
     pub l1_header: AlloyConsensusHeader,
+    /// The tx list calldata, or the raw blobs attached to the propose tx concatenated in order
     pub tx_list: Vec<u8>,
     pub anchor_tx: String,
     pub block_proposed: BlockProposed,
     pub prover_data: TaikoProverData,
-    pub tx_blob_hash: Option<B256>,
+    /// The versioned hashes of the blobs attached to the propose tx
+    pub tx_blob_hashes: Vec<B256>,
+    /// The KZG commitment and proof of every blob, in the same order as the blob hashes
+    pub tx_blob_proofs: Vec<BlobProof>,
+}
+
+/// The KZG commitment of a blob with the proof that the blob data matches it.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct BlobProof {
+    pub commitment: FixedBytes<48>,
+    pub proof: FixedBytes<48>,
+}
+
+/// External input of a batch of blocks proposed together on L1.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GuestBatchInput {
+    /// The blocks of the batch in order, every block builds on top of the previous one
+    pub inputs: Vec<GuestInput>,
+    /// The proposal of the batch
+    pub batch_proposed: taiko_batch::BatchProposed,
+    /// The tx list of the whole batch, or the raw blobs concatenated in their committed order
+    pub tx_list: Vec<u8>,
+    /// The KZG commitment and proof of every blob of the batch
+    pub tx_blob_proofs: Vec<BlobProof>,
 }
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
@@ -287,6 +311,84 @@ pub mod taiko_a6 {
     }
 }
 
+/// The batch based protocol, where a single proposal commits to multiple L2 blocks.
+pub mod taiko_batch {
+    use alloy_sol_types::sol;
+    use serde::{Deserialize, Serialize};
+
+    sol! {
+        #[derive(Debug, Default, Deserialize, Serialize)]
+        struct BaseFeeConfig {
+            uint8 adjustmentQuotient;
+            uint8 sharingPctg;
+            uint32 gasIssuancePerSecond;
+            uint64 minGasExcess;
+            uint32 maxGasIssuancePerBlock;
+        }
+
+        #[derive(Debug, Default, Deserialize, Serialize)]
+        struct BlockParams {
+            uint16 numTransactions;
+            uint8 timeShift;
+            bytes32[] signalSlots;
+        }
+
+        #[derive(Debug, Default, Deserialize, Serialize)]
+        struct BatchInfo {
+            bytes32 txsHash;
+            BlockParams[] blocks;
+            bytes32[] blobHashes;
+            bytes32 extraData;
+            address coinbase;
+            uint64 proposedIn;
+            uint32 blobCreatedIn;
+            uint32 blobByteOffset;
+            uint32 blobByteSize;
+            uint32 gasLimit;
+            uint64 lastBlockId;
+            uint64 lastBlockTimestamp;
+            uint64 anchorBlockId;
+            bytes32 anchorBlockHash;
+            BaseFeeConfig baseFeeConfig;
+        }
+
+        #[derive(Debug, Default, Deserialize, Serialize)]
+        struct BatchMetadata {
+            bytes32 infoHash;
+            address proposer;
+            uint64 batchId;
+            uint64 proposedAt;
+        }
+
+        #[derive(Debug)]
+        struct Transition {
+            bytes32 parentHash;
+            bytes32 blockHash;
+            bytes32 stateRoot;
+        }
+
+        #[derive(Debug, Default, Clone, Deserialize, Serialize)]
+        event BatchProposed(BatchInfo info, BatchMetadata meta, bytes txList);
+
+        function anchorV3(
+            uint64 _anchorBlockId,
+            bytes32 _anchorStateRoot,
+            uint32 _parentGasUsed,
+            BaseFeeConfig _baseFeeConfig,
+            bytes32[] _signalSlots
+        )
+            external
+        {}
+    }
+
+    /// Decodes the anchor tx of the blocks proposed in a batch.
+    #[inline]
+    pub fn decode_anchor_v3(bytes: &[u8]) -> anyhow::Result<anchorV3Call> {
+        use alloy_sol_types::SolCall;
+        anchorV3Call::abi_decode(bytes, true).map_err(|e| anyhow::anyhow!(e))
+    }
+}
+
 impl From<taiko_a6::EthDeposit> for EthDeposit {
     fn from(item: taiko_a6::EthDeposit) -> Self {
         EthDeposit {
@@ -1,16 +1,22 @@
 use alloy_consensus::Header as AlloyConsensusHeader;
 use alloy_primitives::{Address, TxHash, B256};
+use alloy_rpc_types::Transaction as AlloyTransaction;
 use alloy_sol_types::SolValue;
-use anyhow::{ensure, Result};
+use anyhow::{anyhow, ensure, Context, Result};
 use raiko_primitives::keccak::keccak;
 
-use super::taiko_utils::ANCHOR_GAS_LIMIT;
+use super::taiko_utils::{ANCHOR_GAS_LIMIT, ANCHOR_V3_GAS_LIMIT};
 #[cfg(not(feature = "std"))]
 use crate::no_std::*;
 use crate::{
-    consts::get_network_spec,
-    input::{BlockMetadata, EthDeposit, GuestInput, Transition},
-    taiko_utils::HeaderHasher,
+    consts::{get_network_spec, ChainSpec},
+    input::{
+        taiko_batch::{decode_anchor_v3, BatchInfo, BatchMetadata, Transition as BatchTransition},
+        BlockMetadata, EthDeposit, GuestBatchInput, GuestInput, Transition,
+    },
+    taiko_utils::{
+        decode_batch_transactions, generate_transactions, verify_blob_proofs, HeaderHasher,
+    },
 };
 
 #[derive(Debug)]
@@ -85,7 +91,18 @@ pub fn assemble_protocol_instance(
 ) -> Result<ProtocolInstance> {
     let blob_used = input.taiko.block_proposed.meta.blobUsed;
     let tx_list_hash = if blob_used {
-        input.taiko.tx_blob_hash.unwrap()
+        verify_blob_proofs(
+            &input.taiko.tx_list,
+            &input.taiko.tx_blob_hashes,
+            &input.taiko.tx_blob_proofs,
+        )?;
+        // The block stores its tx list in one of the blobs attached to the propose tx
+        let blob_hash = input.taiko.block_proposed.meta.blobHash;
+        ensure!(
+            input.taiko.tx_blob_hashes.contains(&blob_hash),
+            "blob {blob_hash} used by the block is not part of the input"
+        );
+        blob_hash
     } else {
         TxHash::from(keccak(input.taiko.tx_list.as_slice()))
     };
@@ -101,7 +118,21 @@ pub fn assemble_protocol_instance(
         .collect::<Vec<_>>();
 
     let chain_spec = get_network_spec(input.network);
-    let gas_limit: u64 = header.gas_limit;
+    ensure!(
+        !chain_spec.is_batch_block(header.number, header.timestamp),
+        "block {} is proposed in a batch, it can only be proven together with its batch",
+        header.number
+    );
+    // Ethereum blocks have no anchor transaction and are not proposed on an L1
+    let (gas_limit, l1_hash, l1_height) = if chain_spec.is_taiko {
+        (
+            header.gas_limit - ANCHOR_GAS_LIMIT,
+            input.taiko.l1_header.hash(),
+            input.taiko.l1_header.number,
+        )
+    } else {
+        (header.gas_limit, B256::ZERO, 0)
+    };
     let pi = ProtocolInstance {
         transition: Transition {
             parentHash: header.parent_hash,
@@ -110,16 +141,16 @@ pub fn assemble_protocol_instance(
             graffiti: input.taiko.prover_data.graffiti,
         },
         block_metadata: BlockMetadata {
-            l1Hash: input.taiko.l1_header.hash(),
+            l1Hash: l1_hash,
             difficulty: input.taiko.block_proposed.meta.difficulty,
             blobHash: tx_list_hash,
             extraData: bytes_to_bytes32(&header.extra_data).into(),
             depositsHash: keccak(deposits.abi_encode()).into(),
             coinbase: header.beneficiary,
             id: header.number,
-            gasLimit: (gas_limit - ANCHOR_GAS_LIMIT) as u32,
+            gasLimit: gas_limit as u32,
             timestamp: header.timestamp,
-            l1Height: input.taiko.l1_header.number,
+            l1Height: l1_height,
             minTier: input.taiko.block_proposed.meta.minTier,
             blobUsed: blob_used,
             parentMetaHash: input.taiko.block_proposed.meta.parentMetaHash,
@@ -131,7 +162,7 @@ pub fn assemble_protocol_instance(
     };
 
     // Sanity check
-    if input.network.is_taiko() {
+    if chain_spec.is_taiko {
         ensure!(
             pi.block_metadata.abi_encode() == input.taiko.block_proposed.meta.abi_encode(),
             format!(
@@ -144,6 +175,226 @@ pub fn assemble_protocol_instance(
     Ok(pi)
 }
 
+/// The public input of a batch: the transition from the parent of the first block to the last
+/// block of the batch, and the metadata of the batch proposal.
+#[derive(Debug)]
+pub struct BatchProtocolInstance {
+    pub transition: BatchTransition,
+    pub batch_metadata: BatchMetadata,
+    pub chain_id: u64,
+    pub sgx_verifier_address: Address,
+    pub risc0_verifier_address: Address,
+    pub sp1_verifier_address: Address,
+}
+
+impl BatchProtocolInstance {
+    pub fn meta_hash(&self) -> B256 {
+        keccak(self.batch_metadata.abi_encode()).into()
+    }
+
+    // keccak256(abi.encode("VERIFY_PROOF", chainId, verifier, tran, newInstance, metaHash))
+    pub fn instance_hash(&self, evidence_type: EvidenceType) -> B256 {
+        // Every verifier commits to its own address, the zk proofs don't have an instance
+        let (verifier_address, new_instance) = match evidence_type {
+            EvidenceType::Sgx { new_pubkey } => (self.sgx_verifier_address, new_pubkey),
+            EvidenceType::Risc0 => (self.risc0_verifier_address, Address::ZERO),
+            EvidenceType::Succinct => (self.sp1_verifier_address, Address::ZERO),
+            EvidenceType::PseZk | EvidenceType::Powdr => todo!(),
+            // Not verified onchain
+            EvidenceType::Native => (Address::ZERO, Address::ZERO),
+        };
+        keccak(
+            (
+                "VERIFY_PROOF",
+                self.chain_id,
+                verifier_address,
+                self.transition.clone(),
+                new_instance,
+                self.meta_hash(),
+            )
+                .abi_encode_params(),
+        )
+        .into()
+    }
+}
+
+/// The hash the batch info commits to for the tx list, the blob data is committed to by the
+/// blob hashes so the proposal tx list is empty then.
+pub fn batch_txs_hash(tx_list: &[u8], blob_hashes: &[B256]) -> B256 {
+    let tx_list_hash: B256 = if blob_hashes.is_empty() {
+        keccak(tx_list).into()
+    } else {
+        keccak(&[] as &[u8]).into()
+    };
+    keccak((tx_list_hash, blob_hashes.to_vec()).abi_encode_params()).into()
+}
+
+/// Checks the blocks of the batch against the batch proposal and assembles the public input.
+pub fn assemble_batch_protocol_instance(
+    input: &GuestBatchInput,
+    headers: &[AlloyConsensusHeader],
+) -> Result<BatchProtocolInstance> {
+    ensure!(!input.inputs.is_empty(), "batch without blocks");
+    assemble_batch(input, headers, &get_network_spec(input.inputs[0].network))
+}
+
+fn assemble_batch(
+    input: &GuestBatchInput,
+    headers: &[AlloyConsensusHeader],
+    chain_spec: &ChainSpec,
+) -> Result<BatchProtocolInstance> {
+    let BatchInfo {
+        txsHash,
+        blocks,
+        blobHashes,
+        extraData,
+        coinbase,
+        gasLimit,
+        lastBlockId,
+        lastBlockTimestamp,
+        anchorBlockId,
+        anchorBlockHash,
+        baseFeeConfig,
+        ..
+    } = &input.batch_proposed.info;
+    let meta = &input.batch_proposed.meta;
+
+    // The batch info is only committed to through its hash
+    ensure!(
+        B256::from(keccak(input.batch_proposed.info.abi_encode())) == meta.infoHash,
+        "batch info hash mismatch"
+    );
+    ensure!(
+        !blocks.is_empty() && blocks.len() == input.inputs.len() && blocks.len() == headers.len(),
+        "expected {} blocks in batch {}, got {}",
+        blocks.len(),
+        meta.batchId,
+        headers.len()
+    );
+
+    // The tx list
+    if !blobHashes.is_empty() {
+        verify_blob_proofs(&input.tx_list, blobHashes, &input.tx_blob_proofs)?;
+    }
+    let txs_hash = batch_txs_hash(&input.tx_list, blobHashes);
+    ensure!(
+        txs_hash == *txsHash,
+        "batch txs hash mismatch, expected: {txsHash}, got: {txs_hash}"
+    );
+    // Every block executes the next `numTransactions` transactions of the batch
+    let batch_transactions = decode_batch_transactions(&input.tx_list, &input.batch_proposed.info);
+    let num_transactions = blocks
+        .iter()
+        .map(|params| params.numTransactions as usize)
+        .sum::<usize>();
+    ensure!(
+        batch_transactions.len() == num_transactions,
+        "expected {num_transactions} transactions in the batch tx list, got {}",
+        batch_transactions.len()
+    );
+    let mut batch_transactions = batch_transactions.into_iter();
+
+    for (i, ((block_input, header), params)) in
+        input.inputs.iter().zip(headers).zip(blocks).enumerate()
+    {
+        ensure!(
+            block_input.network == input.inputs[0].network,
+            "all the blocks of a batch have to be on the same network"
+        );
+        ensure!(
+            chain_spec.is_batch_block(header.number, header.timestamp),
+            "block {} is not proposed with the batch protocol on {:?}",
+            header.number,
+            block_input.network
+        );
+        // The invalid transactions of the slice are skipped by the builder, same as for a
+        // single block
+        let transactions = generate_transactions(false, &block_input.taiko.tx_list, None);
+        let expected_transactions = batch_transactions
+            .by_ref()
+            .take(params.numTransactions as usize)
+            .collect::<Vec<_>>();
+        ensure!(
+            transactions == expected_transactions,
+            "transactions of block {} don't match the batch tx list",
+            header.number
+        );
+        ensure!(
+            header.beneficiary == *coinbase,
+            "coinbase mismatch in block {}",
+            header.number
+        );
+        ensure!(
+            header.gas_limit == *gasLimit as u64 + ANCHOR_V3_GAS_LIMIT,
+            "gas limit mismatch in block {}",
+            header.number
+        );
+        ensure!(
+            header.extra_data.len() <= 32
+                && B256::from(bytes_to_bytes32(&header.extra_data)) == *extraData,
+            "extra data mismatch in block {}",
+            header.number
+        );
+        // All the blocks are anchored to the same L1 block
+        ensure!(
+            block_input.taiko.l1_header.number == *anchorBlockId
+                && block_input.taiko.l1_header.hash() == *anchorBlockHash,
+            "anchor block mismatch in block {}",
+            header.number
+        );
+        // The rest of the anchor tx is checked against the block by the builder
+        let anchor_tx: AlloyTransaction = serde_json::from_str(&block_input.taiko.anchor_tx)
+            .map_err(|e| anyhow!(e))
+            .with_context(|| format!("invalid anchor tx in block {}", header.number))?;
+        let anchor_call = decode_anchor_v3(&anchor_tx.input)?;
+        ensure!(
+            anchor_call._baseFeeConfig.abi_encode() == baseFeeConfig.abi_encode(),
+            "base fee config mismatch in block {}",
+            header.number
+        );
+        ensure!(
+            anchor_call._signalSlots == params.signalSlots,
+            "signal slots mismatch in block {}",
+            header.number
+        );
+        if i > 0 {
+            // The transition only commits to the first parent and the last block
+            ensure!(
+                header.parent_hash == headers[i - 1].hash(),
+                "block {} does not build on block {} of the batch",
+                header.number,
+                headers[i - 1].number
+            );
+            ensure!(
+                header.timestamp == headers[i - 1].timestamp + params.timeShift as u64,
+                "timestamp mismatch in block {}",
+                header.number
+            );
+        }
+    }
+
+    let (first, last) = (&headers[0], &headers[headers.len() - 1]);
+    ensure!(
+        last.number == *lastBlockId && last.timestamp == *lastBlockTimestamp,
+        "last block mismatch, expected block {lastBlockId} at {lastBlockTimestamp}, got block {} at {}",
+        last.number,
+        last.timestamp
+    );
+
+    Ok(BatchProtocolInstance {
+        transition: BatchTransition {
+            parentHash: first.parent_hash,
+            blockHash: last.hash(),
+            stateRoot: last.state_root,
+        },
+        batch_metadata: meta.clone(),
+        chain_id: chain_spec.chain_id,
+        sgx_verifier_address: chain_spec.sgx_verifier_address.unwrap_or_default(),
+        risc0_verifier_address: chain_spec.risc0_verifier_address.unwrap_or_default(),
+        sp1_verifier_address: chain_spec.sp1_verifier_address.unwrap_or_default(),
+    })
+}
+
 fn bytes_to_bytes32(input: &[u8]) -> [u8; 32] {
     let mut bytes = [0u8; 32];
     let len = core::cmp::min(input.len(), 32);
@@ -153,12 +404,312 @@ fn bytes_to_bytes32(input: &[u8]) -> [u8; 32] {
 
 #[cfg(test)]
 mod tests {
-    use alloy_primitives::{address, b256};
+    use std::io::Write;
+
+    use alloy_consensus::{SignableTransaction, TxEnvelope, TxLegacy};
+    use alloy_primitives::{address, b256, Bytes, Signature, TxKind, U256};
     use alloy_sol_types::SolCall;
+    use libflate::zlib::Encoder as zlibEncoder;
     use raiko_primitives::keccak;
 
     use super::*;
-    use crate::input::{proveBlockCall, BlockMetadata, TierProof, Transition};
+    use crate::{
+        consts::{ForkCondition, Network, TAIKO_A7_CHAIN_SPEC},
+        input::{
+            proveBlockCall,
+            taiko_batch::{anchorV3Call, BaseFeeConfig, BatchProposed, BlockParams},
+            BlockMetadata, TaikoGuestInput, TierProof, Transition,
+        },
+    };
+
+    fn batch_chain_spec() -> ChainSpec {
+        ChainSpec {
+            batch_fork: ForkCondition::Block(0),
+            ..TAIKO_A7_CHAIN_SPEC.clone()
+        }
+    }
+
+    fn batch_tx(nonce: u64) -> TxEnvelope {
+        TxEnvelope::Legacy(
+            TxLegacy {
+                chain_id: None,
+                nonce,
+                gas_price: 1,
+                gas_limit: 21_000,
+                to: TxKind::Call(Address::with_last_byte(1)),
+                value: U256::ZERO,
+                input: Default::default(),
+            }
+            .into_signed(
+                Signature::from_rs_and_parity(U256::from(1), U256::from(1), 27u64).unwrap(),
+            ),
+        )
+    }
+
+    /// A batch of two blocks on top of each other, with two and one transactions.
+    fn test_batch() -> (GuestBatchInput, Vec<AlloyConsensusHeader>) {
+        let transactions = (0..3).map(batch_tx).collect::<Vec<_>>();
+        let mut encoder = zlibEncoder::new(Vec::new()).unwrap();
+        encoder
+            .write_all(&alloy_rlp::encode(&transactions))
+            .unwrap();
+        let tx_list = encoder.finish().into_result().unwrap();
+
+        let l1_header = AlloyConsensusHeader {
+            number: 1000,
+            ..Default::default()
+        };
+        let base_fee_config = BaseFeeConfig {
+            adjustmentQuotient: 8,
+            sharingPctg: 75,
+            gasIssuancePerSecond: 5_000_000,
+            minGasExcess: 1_340_000_000,
+            maxGasIssuancePerBlock: 600_000_000,
+        };
+        let blocks = vec![
+            BlockParams {
+                numTransactions: 2,
+                timeShift: 0,
+                signalSlots: vec![],
+            },
+            BlockParams {
+                numTransactions: 1,
+                timeShift: 12,
+                signalSlots: vec![B256::with_last_byte(7)],
+            },
+        ];
+        let coinbase = Address::with_last_byte(2);
+        let extra_data = B256::with_last_byte(3);
+
+        let mut headers: Vec<AlloyConsensusHeader> = Vec::new();
+        let mut inputs = Vec::new();
+        let mut txs = transactions.iter();
+        for (i, params) in blocks.iter().enumerate() {
+            let header = AlloyConsensusHeader {
+                parent_hash: headers
+                    .last()
+                    .map(|h| h.hash())
+                    .unwrap_or(B256::with_last_byte(4)),
+                number: 100 + i as u64,
+                timestamp: 1000 + 12 * i as u64,
+                beneficiary: coinbase,
+                gas_limit: 30_000_000 + ANCHOR_V3_GAS_LIMIT,
+                extra_data: Bytes::from(extra_data.to_vec()),
+                ..Default::default()
+            };
+            let anchor_tx = AlloyTransaction {
+                input: anchorV3Call {
+                    _anchorBlockId: l1_header.number,
+                    _anchorStateRoot: l1_header.state_root,
+                    _parentGasUsed: 0,
+                    _baseFeeConfig: base_fee_config.clone(),
+                    _signalSlots: params.signalSlots.clone(),
+                }
+                .abi_encode()
+                .into(),
+                ..Default::default()
+            };
+            let block_txs = txs
+                .by_ref()
+                .take(params.numTransactions as usize)
+                .cloned()
+                .collect::<Vec<_>>();
+            inputs.push(GuestInput {
+                network: Network::TaikoA7,
+                block_number: header.number,
+                timestamp: header.timestamp,
+                taiko: TaikoGuestInput {
+                    l1_header: l1_header.clone(),
+                    tx_list: alloy_rlp::encode(&block_txs),
+                    anchor_tx: serde_json::to_string(&anchor_tx).unwrap(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            headers.push(header);
+        }
+
+        let info = BatchInfo {
+            txsHash: batch_txs_hash(&tx_list, &[]),
+            blocks,
+            extraData: extra_data,
+            coinbase,
+            gasLimit: 30_000_000,
+            lastBlockId: 101,
+            lastBlockTimestamp: 1012,
+            anchorBlockId: l1_header.number,
+            anchorBlockHash: l1_header.hash(),
+            baseFeeConfig: base_fee_config,
+            ..Default::default()
+        };
+        let meta = BatchMetadata {
+            infoHash: keccak::keccak(info.abi_encode()).into(),
+            batchId: 1,
+            ..Default::default()
+        };
+        let input = GuestBatchInput {
+            inputs,
+            batch_proposed: BatchProposed {
+                info,
+                meta,
+                ..Default::default()
+            },
+            tx_list,
+            ..Default::default()
+        };
+        (input, headers)
+    }
+
+    /// Commits to the modified batch info again, so only the modified field is wrong.
+    fn rehash(input: &mut GuestBatchInput) {
+        input.batch_proposed.meta.infoHash =
+            keccak::keccak(input.batch_proposed.info.abi_encode()).into();
+    }
+
+    fn assert_rejected(input: &GuestBatchInput, headers: &[AlloyConsensusHeader], error: &str) {
+        let err = assemble_batch(input, headers, &batch_chain_spec()).unwrap_err();
+        assert!(err.to_string().contains(error), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_assemble_batch() {
+        let (input, headers) = test_batch();
+        let pi = assemble_batch(&input, &headers, &batch_chain_spec()).unwrap();
+        assert_eq!(pi.transition.parentHash, B256::with_last_byte(4));
+        assert_eq!(pi.transition.blockHash, headers[1].hash());
+        assert_eq!(pi.batch_metadata.batchId, 1);
+
+        // Blocks before the batch fork are never part of a batch
+        let err = assemble_batch(&input, &headers, &TAIKO_A7_CHAIN_SPEC).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("block 100 is not proposed with the batch protocol"));
+    }
+
+    /// `LibPublicInput.hashPublicInputs` encoded by hand, word by word:
+    /// `abi.encode("VERIFY_PROOF", chainId, verifier, transition, newInstance, metaHash)`
+    fn hash_public_inputs(
+        chain_id: u64,
+        verifier: Address,
+        transition: &BatchTransition,
+        new_instance: Address,
+        meta_hash: B256,
+    ) -> B256 {
+        let mut verify_proof = [0u8; 32];
+        verify_proof[..12].copy_from_slice(b"VERIFY_PROOF");
+        let words: [B256; 10] = [
+            // offset of the string after the 8 head words
+            U256::from(8 * 32).to_be_bytes::<32>().into(),
+            U256::from(chain_id).to_be_bytes::<32>().into(),
+            verifier.into_word(),
+            transition.parentHash,
+            transition.blockHash,
+            transition.stateRoot,
+            new_instance.into_word(),
+            meta_hash,
+            U256::from(12).to_be_bytes::<32>().into(),
+            verify_proof.into(),
+        ];
+        keccak::keccak(words.iter().flat_map(|word| word.0).collect::<Vec<u8>>()).into()
+    }
+
+    #[test]
+    fn test_batch_instance_hash() {
+        let (input, headers) = test_batch();
+        let spec = ChainSpec {
+            risc0_verifier_address: Some(Address::with_last_byte(2)),
+            sp1_verifier_address: Some(Address::with_last_byte(3)),
+            ..batch_chain_spec()
+        };
+        let pi = assemble_batch(&input, &headers, &spec).unwrap();
+        let new_instance = Address::with_last_byte(4);
+        assert_eq!(
+            pi.instance_hash(EvidenceType::Sgx {
+                new_pubkey: new_instance
+            }),
+            hash_public_inputs(
+                spec.chain_id,
+                spec.sgx_verifier_address.unwrap(),
+                &pi.transition,
+                new_instance,
+                pi.meta_hash()
+            )
+        );
+        assert_eq!(
+            pi.instance_hash(EvidenceType::Risc0),
+            hash_public_inputs(
+                spec.chain_id,
+                Address::with_last_byte(2),
+                &pi.transition,
+                Address::ZERO,
+                pi.meta_hash()
+            )
+        );
+        assert_eq!(
+            pi.instance_hash(EvidenceType::Succinct),
+            hash_public_inputs(
+                spec.chain_id,
+                Address::with_last_byte(3),
+                &pi.transition,
+                Address::ZERO,
+                pi.meta_hash()
+            )
+        );
+    }
+
+    #[test]
+    fn test_batch_rejected() {
+        let (input, mut headers) = test_batch();
+        headers[1].parent_hash = B256::with_last_byte(5);
+        assert_rejected(&input, &headers, "block 101 does not build on block 100");
+
+        let (mut input, headers) = test_batch();
+        input.batch_proposed.info.blocks[1].numTransactions = 2;
+        rehash(&mut input);
+        assert_rejected(&input, &headers, "expected 4 transactions");
+
+        let (mut input, headers) = test_batch();
+        input.batch_proposed.info.blocks[0].numTransactions = 1;
+        input.batch_proposed.info.blocks[1].numTransactions = 2;
+        rehash(&mut input);
+        assert_rejected(&input, &headers, "transactions of block 100 don't match");
+
+        let (input, mut headers) = test_batch();
+        headers[0].beneficiary = Address::with_last_byte(6);
+        assert_rejected(&input, &headers, "coinbase mismatch in block 100");
+
+        let (input, mut headers) = test_batch();
+        headers[0].gas_limit = 30_000_000 + ANCHOR_GAS_LIMIT;
+        assert_rejected(&input, &headers, "gas limit mismatch in block 100");
+
+        let (input, mut headers) = test_batch();
+        headers[0].extra_data = Bytes::from_static(b"extra");
+        assert_rejected(&input, &headers, "extra data mismatch in block 100");
+
+        let (mut input, headers) = test_batch();
+        input.batch_proposed.info.blocks[1].timeShift = 11;
+        rehash(&mut input);
+        assert_rejected(&input, &headers, "timestamp mismatch in block 101");
+
+        let (mut input, headers) = test_batch();
+        input.batch_proposed.info.blocks[1].signalSlots = vec![];
+        rehash(&mut input);
+        assert_rejected(&input, &headers, "signal slots mismatch in block 101");
+
+        let (mut input, headers) = test_batch();
+        input.batch_proposed.info.baseFeeConfig.sharingPctg = 50;
+        rehash(&mut input);
+        assert_rejected(&input, &headers, "base fee config mismatch in block 100");
+
+        let (mut input, headers) = test_batch();
+        input.batch_proposed.info.lastBlockId = 102;
+        rehash(&mut input);
+        assert_rejected(&input, &headers, "last block mismatch");
+
+        let (mut input, headers) = test_batch();
+        input.batch_proposed.info.coinbase = Address::with_last_byte(6);
+        assert_rejected(&input, &headers, "batch info hash mismatch");
+    }
 
     #[test]
     fn bytes_to_bytes32_test() {
@@ -173,6 +724,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_batch_txs_hash() {
+        // abi.encode(bytes32, bytes32[]) without the leading offset of a dynamic tuple
+        let blob_hashes = [B256::with_last_byte(1), B256::with_last_byte(2)];
+        let mut encoded = keccak::keccak(&[] as &[u8]).to_vec();
+        encoded.extend(B256::with_last_byte(0x40));
+        encoded.extend(B256::with_last_byte(2));
+        encoded.extend(blob_hashes.iter().flat_map(|h| h.0));
+        assert_eq!(
+            batch_txs_hash(&[1, 2, 3], &blob_hashes),
+            B256::from(keccak::keccak(&encoded))
+        );
+
+        // calldata
+        let mut encoded = keccak::keccak([1u8, 2, 3]).to_vec();
+        encoded.extend(B256::with_last_byte(0x40));
+        encoded.extend(B256::ZERO);
+        assert_eq!(
+            batch_txs_hash(&[1, 2, 3], &[]),
+            B256::from(keccak::keccak(&encoded))
+        );
+    }
+
     #[test]
     fn test_calc_eip712_pi_hash() {
         let trans = Transition {
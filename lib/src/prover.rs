@@ -5,8 +5,8 @@ use serde::Serialize;
 use thiserror::Error as ThisError;
 
 use crate::{
-    input::{GuestInput, GuestOutput},
-    protocol_instance::ProtocolInstance,
+    input::{GuestBatchInput, GuestInput, GuestOutput},
+    protocol_instance::{BatchProtocolInstance, EvidenceType, ProtocolInstance},
 };
 
 #[derive(ThisError, Debug)]
@@ -35,12 +35,28 @@ pub type Proof = serde_json::Value;
 pub trait Prover {
     #[allow(async_fn_in_trait)]
     async fn run(
-        input: GuestInput,
+        input: &GuestInput,
         output: GuestOutput,
         config: &ProverConfig,
     ) -> ProverResult<Proof>;
 
     fn instance_hash(pi: ProtocolInstance) -> B256;
+
+    /// Proves all the blocks of a batch with a single proof.
+    #[allow(async_fn_in_trait)]
+    async fn run_batch(
+        _input: &GuestBatchInput,
+        _output: GuestOutput,
+        _config: &ProverConfig,
+    ) -> ProverResult<Proof> {
+        Err(ProverError::GuestError(
+            "batch proving is not supported by this prover".to_string(),
+        ))
+    }
+
+    fn batch_instance_hash(pi: BatchProtocolInstance) -> B256 {
+        pi.instance_hash(EvidenceType::Native)
+    }
 }
 
 pub fn to_proof(proof: ProverResult<impl Serialize>) -> ProverResult<Proof> {
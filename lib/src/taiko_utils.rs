@@ -9,16 +9,23 @@ use alloy_rpc_types::{Header as AlloyHeader, Transaction as AlloyTransaction};
 use anyhow::{anyhow, bail, ensure, Context, Result};
 use libflate::zlib::Decoder as zlibDecoder;
 use once_cell::unsync::Lazy;
-use raiko_primitives::{keccak256, B256};
+use raiko_primitives::{keccak256, FixedBytes, B256};
+use sha2::{Digest, Sha256};
 
 #[cfg(not(feature = "std"))]
 use crate::no_std::*;
 use crate::{
     consts::{get_network_spec, Network},
-    input::{decode_anchor, GuestInput},
+    input::{
+        decode_anchor,
+        taiko_batch::{decode_anchor_v3, BatchInfo},
+        BlobProof, BlockMetadata, GuestInput,
+    },
 };
 
 pub const ANCHOR_GAS_LIMIT: u64 = 250_000;
+/// The gas limit of the anchor tx of the blocks proposed in a batch
+pub const ANCHOR_V3_GAS_LIMIT: u64 = 1_000_000;
 pub const GOLDEN_TOUCH_ACCOUNT: Lazy<Address> = Lazy::new(|| {
     Address::from_str("0x0000777735367b36bC9B61C50022d9D0700dB4Ec")
         .expect("invalid golden touch account")
@@ -42,7 +49,11 @@ pub fn generate_transactions(
 ) -> Vec<TxEnvelope> {
     // Decode the tx list from the raw data posted onchain
     let tx_list = &if is_blob_data {
-        let compressed_tx_list = decode_blob_data(tx_list);
+        // The compressed tx list can span multiple blobs
+        let compressed_tx_list = tx_list
+            .chunks(BLOB_DATA_CAPACITY)
+            .flat_map(decode_blob_data)
+            .collect::<Vec<u8>>();
         zlib_decompress_blob(&compressed_tx_list).unwrap_or_default()
     } else {
         tx_list.to_owned()
@@ -85,7 +96,7 @@ pub fn generate_transactions(
 
 const BLOB_FIELD_ELEMENT_NUM: usize = 4096;
 const BLOB_FIELD_ELEMENT_BYTES: usize = 32;
-const BLOB_DATA_CAPACITY: usize = BLOB_FIELD_ELEMENT_NUM * BLOB_FIELD_ELEMENT_BYTES;
+pub const BLOB_DATA_CAPACITY: usize = BLOB_FIELD_ELEMENT_NUM * BLOB_FIELD_ELEMENT_BYTES;
 const BLOB_VERSION_OFFSET: usize = 1;
 const BLOB_ENCODING_VERSION: u8 = 0;
 const MAX_BLOB_DATA_SIZE: usize = (4 * 31 + 3) * 1024 - 4;
@@ -193,6 +204,146 @@ fn zlib_decompress_blob(blob: &[u8]) -> Result<Vec<u8>> {
     Ok(decoded_buf)
 }
 
+/// The tx list of the proposed block.
+///
+/// The propose tx can carry more blobs than the one the block stores its tx list in, all of
+/// them are part of the input but only the one the metadata commits to is decoded.
+pub fn block_tx_list<'a>(
+    meta: &BlockMetadata,
+    tx_list: &'a [u8],
+    blob_hashes: &[B256],
+) -> &'a [u8] {
+    if !meta.blobUsed {
+        return tx_list;
+    }
+    blob_hashes
+        .iter()
+        .position(|blob_hash| *blob_hash == meta.blobHash)
+        .and_then(|i| tx_list.get(i * BLOB_DATA_CAPACITY..(i + 1) * BLOB_DATA_CAPACITY))
+        .unwrap_or_default()
+}
+
+const VERSIONED_HASH_VERSION_KZG: u8 = 1;
+
+/// Calculates the versioned hash of a KZG commitment as defined in EIP-4844.
+pub fn commitment_to_versioned_hash(commitment: &FixedBytes<48>) -> B256 {
+    let mut versioned_hash: [u8; 32] = Sha256::digest(commitment).into();
+    versioned_hash[0] = VERSIONED_HASH_VERSION_KZG;
+    B256::from(versioned_hash)
+}
+
+/// Checks that the tx list is made up of exactly the blobs with the given versioned hashes
+/// using the KZG proofs of the blobs.
+///
+/// Unlike recomputing the commitments this doesn't need c-kzg, so it runs in all guests.
+pub fn verify_blob_proofs(
+    tx_list: &[u8],
+    blob_hashes: &[B256],
+    blob_proofs: &[BlobProof],
+) -> Result<()> {
+    ensure!(!blob_hashes.is_empty(), "no blob hashes for blob tx list");
+    ensure!(
+        tx_list.len() == blob_hashes.len() * BLOB_DATA_CAPACITY,
+        "expected {} blobs, got {} bytes",
+        blob_hashes.len(),
+        tx_list.len()
+    );
+    ensure!(
+        blob_proofs.len() == blob_hashes.len(),
+        "expected {} blob proofs, got {}",
+        blob_hashes.len(),
+        blob_proofs.len()
+    );
+    let kzg_settings = kzg_rs::KzgSettings::load_trusted_setup_file()
+        .map_err(|e| anyhow!("failed to load the KZG trusted setup: {e:?}"))?;
+    for (i, ((blob, blob_hash), blob_proof)) in tx_list
+        .chunks(BLOB_DATA_CAPACITY)
+        .zip(blob_hashes)
+        .zip(blob_proofs)
+        .enumerate()
+    {
+        let versioned_hash = commitment_to_versioned_hash(&blob_proof.commitment);
+        ensure!(
+            versioned_hash == *blob_hash,
+            "blob {i} hash mismatch, expected: {blob_hash}, got: {versioned_hash}"
+        );
+        let valid = kzg_rs::KzgProof::verify_blob_kzg_proof(
+            kzg_rs::Blob::from_slice(blob).map_err(|e| anyhow!("invalid blob {i}: {e:?}"))?,
+            &kzg_rs::Bytes48::from_slice(blob_proof.commitment.as_slice())
+                .map_err(|e| anyhow!("invalid commitment for blob {i}: {e:?}"))?,
+            &kzg_rs::Bytes48::from_slice(blob_proof.proof.as_slice())
+                .map_err(|e| anyhow!("invalid proof for blob {i}: {e:?}"))?,
+            &kzg_settings,
+        )
+        .map_err(|e| anyhow!("failed to verify blob {i}: {e:?}"))?;
+        ensure!(valid, "blob {i} doesn't match its commitment");
+    }
+    Ok(())
+}
+
+/// Computes the KZG commitment and proof of every blob of the tx list.
+#[cfg(feature = "kzg")]
+pub fn blob_proofs(tx_list: &[u8]) -> Result<Vec<BlobProof>> {
+    use raiko_primitives::{
+        eip4844::MAINNET_KZG_TRUSTED_SETUP,
+        kzg::{Blob, KzgCommitment, KzgProof},
+    };
+
+    tx_list
+        .chunks(BLOB_DATA_CAPACITY)
+        .map(|blob| {
+            let blob = Blob::from_bytes(blob).map_err(|e| anyhow!("invalid blob: {e:?}"))?;
+            let commitment =
+                KzgCommitment::blob_to_kzg_commitment(&blob, &MAINNET_KZG_TRUSTED_SETUP)
+                    .map_err(|e| anyhow!("failed to compute blob commitment: {e:?}"))?
+                    .to_bytes();
+            let proof =
+                KzgProof::compute_blob_kzg_proof(&blob, &commitment, &MAINNET_KZG_TRUSTED_SETUP)
+                    .map_err(|e| anyhow!("failed to compute blob proof: {e:?}"))?
+                    .to_bytes();
+            Ok(BlobProof {
+                commitment: FixedBytes::from_slice(commitment.as_slice()),
+                proof: FixedBytes::from_slice(proof.as_slice()),
+            })
+        })
+        .collect()
+}
+
+/// Calculates the versioned hash of the KZG commitment of the blob.
+#[cfg(feature = "kzg")]
+pub fn blob_versioned_hash(blob: &[u8]) -> Result<B256> {
+    use raiko_primitives::{
+        eip4844::{kzg_to_versioned_hash, MAINNET_KZG_TRUSTED_SETUP},
+        kzg::{Blob, KzgCommitment},
+    };
+
+    let blob = Blob::from_bytes(blob).map_err(|e| anyhow!("invalid blob: {e:?}"))?;
+    let commitment = KzgCommitment::blob_to_kzg_commitment(&blob, &MAINNET_KZG_TRUSTED_SETUP)
+        .map_err(|e| anyhow!("failed to compute blob commitment: {e:?}"))?;
+    Ok(kzg_to_versioned_hash(commitment))
+}
+
+/// Decodes all the transactions of a batch.
+///
+/// Batch tx lists are always compressed, the blobs are decoded first and only the
+/// `blobByteSize` bytes at `blobByteOffset` of the blob data hold the tx list.
+pub fn decode_batch_transactions(tx_list: &[u8], info: &BatchInfo) -> Vec<TxEnvelope> {
+    let compressed_tx_list = if info.blobHashes.is_empty() {
+        tx_list.to_owned()
+    } else {
+        let blob_data = tx_list
+            .chunks(BLOB_DATA_CAPACITY)
+            .flat_map(decode_blob_data)
+            .collect::<Vec<u8>>();
+        let start = (info.blobByteOffset as usize).min(blob_data.len());
+        let end = start
+            .saturating_add(info.blobByteSize as usize)
+            .min(blob_data.len());
+        blob_data[start..end].to_vec()
+    };
+    decode_transactions(&zlib_decompress_blob(&compressed_tx_list).unwrap_or_default())
+}
+
 const GX1: Lazy<U256> =
     Lazy::new(|| uint!(0x79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798_U256));
 const N: Lazy<U256> =
@@ -241,6 +392,7 @@ pub fn check_anchor_tx(
             check_anchor_signature(tx).context(anyhow!("failed to check anchor signature"))?;
 
             let tx = tx.tx();
+            let chain_spec = get_network_spec(network);
 
             // Extract the `to` address
             let to = if let TxKind::Call(to_addr) = tx.to {
@@ -255,7 +407,7 @@ pub fn check_anchor_tx(
             );
             // Check that the L2 contract is being called
             ensure!(
-                to == get_network_spec(network).l2_contract.unwrap(),
+                to == chain_spec.l2_contract.unwrap(),
                 "anchor transaction to mismatch"
             );
             // Tx can't have any ETH attached
@@ -263,9 +415,16 @@ pub fn check_anchor_tx(
                 tx.value == U256::from(0),
                 "anchor transaction value mismatch"
             );
+            // The blocks of a batch are anchored with `anchorV3`
+            let is_batch_block = chain_spec.is_batch_block(input.block_number, input.timestamp);
             // Tx needs to have the expected gas limit
+            let anchor_gas_limit = if is_batch_block {
+                ANCHOR_V3_GAS_LIMIT
+            } else {
+                ANCHOR_GAS_LIMIT
+            };
             ensure!(
-                tx.gas_limit == ANCHOR_GAS_LIMIT,
+                tx.gas_limit == anchor_gas_limit,
                 "anchor transaction gas price mismatch"
             );
             // Check needs to have the base fee set to the block base fee
@@ -274,6 +433,24 @@ pub fn check_anchor_tx(
                 "anchor transaction gas mismatch"
             );
 
+            if is_batch_block {
+                // The base fee config and the signal slots are checked against the batch
+                let anchor_call = decode_anchor_v3(&tx.input)?;
+                ensure!(
+                    anchor_call._anchorBlockId == input.taiko.l1_header.number,
+                    "L1 block number mismatch"
+                );
+                ensure!(
+                    anchor_call._anchorStateRoot == input.taiko.l1_header.state_root,
+                    "L1 state root mismatch"
+                );
+                ensure!(
+                    anchor_call._parentGasUsed == input.parent_header.gas_used as u32,
+                    "parentGasUsed mismatch"
+                );
+                return Ok(());
+            }
+
             // Okay now let's decode the anchor tx to verify the inputs
             let anchor_call = decode_anchor(&tx.input)?;
             // The L1 blockhash needs to match the expected value
@@ -343,3 +520,65 @@ pub fn to_header(header: &AlloyHeader) -> AlloyConsensusHeader {
         parent_beacon_block_root: header.parent_beacon_block_root,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_tx_list() {
+        let blob_hashes = [B256::with_last_byte(1), B256::with_last_byte(2)];
+        let tx_list = [vec![1u8; BLOB_DATA_CAPACITY], vec![2u8; BLOB_DATA_CAPACITY]].concat();
+        let mut meta = BlockMetadata {
+            blobUsed: true,
+            blobHash: blob_hashes[1],
+            ..Default::default()
+        };
+        assert_eq!(
+            block_tx_list(&meta, &tx_list, &blob_hashes),
+            &tx_list[BLOB_DATA_CAPACITY..]
+        );
+        meta.blobHash = B256::ZERO;
+        assert!(block_tx_list(&meta, &tx_list, &blob_hashes).is_empty());
+        meta.blobUsed = false;
+        assert_eq!(block_tx_list(&meta, &tx_list, &[]), &tx_list[..]);
+    }
+
+    #[test]
+    fn test_verify_blob_proofs() {
+        // The commitment and proof of the zero blob are both the point at infinity
+        let mut point_at_infinity = [0u8; 48];
+        point_at_infinity[0] = 0xc0;
+        let blob_proof = BlobProof {
+            commitment: point_at_infinity.into(),
+            proof: point_at_infinity.into(),
+        };
+        let zero_blob_hash = alloy_primitives::b256!(
+            "010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014"
+        );
+        assert_eq!(
+            commitment_to_versioned_hash(&blob_proof.commitment),
+            zero_blob_hash
+        );
+
+        let tx_list = vec![0u8; 2 * BLOB_DATA_CAPACITY];
+        let blob_hashes = [zero_blob_hash, zero_blob_hash];
+        let blob_proofs = [blob_proof.clone(), blob_proof.clone()];
+        assert!(verify_blob_proofs(&tx_list, &blob_hashes, &blob_proofs).is_ok());
+        assert!(verify_blob_proofs(&tx_list, &blob_hashes, &blob_proofs[..1]).is_err());
+        assert!(
+            verify_blob_proofs(&tx_list[..BLOB_DATA_CAPACITY], &blob_hashes, &blob_proofs).is_err()
+        );
+        assert!(verify_blob_proofs(
+            &tx_list,
+            &[zero_blob_hash, B256::with_last_byte(1)],
+            &blob_proofs
+        )
+        .is_err());
+
+        // Data that doesn't match the commitment
+        let mut tx_list = tx_list;
+        tx_list[1] = 1;
+        assert!(verify_blob_proofs(&tx_list, &blob_hashes, &blob_proofs).is_err());
+    }
+}
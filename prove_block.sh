@@ -117,7 +117,7 @@ do
   fi
 
   echo "- proving block $block"
-  curl --location --request POST 'http://localhost:8080' \
+  response=$(curl -s --location --request POST 'http://localhost:8080' \
        --header 'Content-Type: application/json' \
        --data-raw "{
          \"jsonrpc\": \"2.0\",
@@ -135,6 +135,12 @@ do
              $proofParam
            }
          ]
-       }"
+       }")
+  # Blocks that are not part of the sampled subset are skipped by the host without a proof
+  if [ "$(echo "$response" | jq -r '.result.sampling.selected')" == "false" ]; then
+    echo "- block $block not selected for proving"
+  else
+    echo "$response"
+  fi
   echo "\\n"
 done
@@ -3,7 +3,7 @@ use risc0_zkvm::guest::env;
 risc0_zkvm::guest::entry!(main);
 
 use raiko_lib::{
-    builder::{BlockBuilderStrategy, TaikoStrategy},
+    builder::build_block,
     input::{GuestInput, GuestOutput, WrappedHeader},
 };
 use raiko_lib::protocol_instance::assemble_protocol_instance;
@@ -12,7 +12,7 @@ use raiko_lib::protocol_instance::EvidenceType;
 fn main() {
 
     let input: GuestInput = env::read();
-    let build_result = TaikoStrategy::build_from(&input);
+    let build_result = build_block(&input);
 
     // TODO: cherry-pick risc0 latest output
     let output = match &build_result {
@@ -4,6 +4,7 @@ use std::{
     env,
     fmt::Debug,
     fs,
+    io::Write,
     path::{Path, PathBuf},
 };
 
@@ -14,8 +15,9 @@ use hex::ToHex;
 use log::{debug, error, info, warn};
 use raiko_lib::{
     input::{GuestInput, GuestOutput},
-    protocol_instance::ProtocolInstance,
+    protocol_instance::{BatchProtocolInstance, EvidenceType, ProtocolInstance},
     prover::{to_proof, Proof, Prover, ProverConfig, ProverResult},
+    Measurement,
 };
 use raiko_primitives::keccak::keccak;
 use risc0_zkvm::{
@@ -26,6 +28,7 @@ use risc0_zkvm::{
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_with::serde_as;
+use tempfile::NamedTempFile;
 use tracing::info as traicing_info;
 
 pub mod snarks;
@@ -45,20 +48,23 @@ pub struct Risc0Param {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Risc0Response {
     pub proof: String,
+    /// Time spent wrapping the STARK into a SNARK, only set when `snark` is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrapping_ms: Option<u64>,
 }
 
 pub struct Risc0Prover;
 
 impl Prover for Risc0Prover {
     async fn run(
-        input: GuestInput,
+        input: &GuestInput,
         output: GuestOutput,
         config: &ProverConfig,
     ) -> ProverResult<Proof> {
         let config = Risc0Param::deserialize(config.get("risc0").unwrap()).unwrap();
 
         println!("elf code length: {}", RISC0_METHODS_ELF.len());
-        let encoded_input = to_vec(&input).expect("Could not serialize proving input!");
+        let encoded_input = to_vec(input).expect("Could not serialize proving input!");
 
         let result = maybe_prove::<GuestInput, GuestOutput>(
             &config,
@@ -72,11 +78,13 @@ impl Prover for Risc0Prover {
         let journal: String = result.clone().unwrap().1.journal.encode_hex();
 
         // Create/verify Groth16 SNARK
+        let mut wrapping_ms = None;
         if config.snark {
             let Some((stark_uuid, stark_receipt)) = result else {
                 panic!("No STARK data to snarkify!");
             };
             let image_id = Digest::from(RISC0_METHODS_ID);
+            let measurement = Measurement::start("Wrapping STARK into SNARK...", false);
             let (snark_uuid, snark_receipt) = stark2snark(image_id, stark_uuid, stark_receipt)
                 .await
                 .map_err(|err| format!("Failed to convert STARK to SNARK: {:?}", err))?;
@@ -86,9 +94,13 @@ impl Prover for Risc0Prover {
             verify_groth16_snark(image_id, snark_receipt)
                 .await
                 .map_err(|err| format!("Failed to verify SNARK: {:?}", err))?;
+            wrapping_ms = Some(measurement.stop_with("=> SNARK generated").as_millis() as u64);
         }
 
-        to_proof(Ok(Risc0Response { proof: journal }))
+        to_proof(Ok(Risc0Response {
+            proof: journal,
+            wrapping_ms,
+        }))
     }
 
     fn instance_hash(pi: ProtocolInstance) -> B256 {
@@ -96,6 +108,10 @@ impl Prover for Risc0Prover {
 
         keccak(data).into()
     }
+
+    fn batch_instance_hash(pi: BatchProtocolInstance) -> B256 {
+        pi.instance_hash(EvidenceType::Risc0)
+    }
 }
 
 pub async fn stark2snark(
@@ -381,13 +397,21 @@ pub fn prove_locally(
         encoded_input.len() * 4 / 1_000_000
     );
 
+    // Hand the input to the executor through a file, so it isn't kept in memory next to the
+    // guest input while proving
+    let mut input_file = NamedTempFile::new().expect("Failed to create input file");
+    input_file
+        .write_all(bytemuck::cast_slice(&encoded_input))
+        .expect("Failed to write input file");
+    drop(encoded_input);
+
     info!("Running the prover...");
     let session = {
         let mut env_builder = ExecutorEnv::builder();
         env_builder
             .session_limit(None)
             .segment_limit_po2(segment_limit_po2)
-            .write_slice(&encoded_input);
+            .stdin(input_file.reopen().expect("Failed to open input file"));
 
         if profile {
             info!("Profiling enabled.");
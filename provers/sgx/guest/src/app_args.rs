@@ -15,7 +15,7 @@ pub struct App {
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
-    /// Prove (i.e. sign) a single block and exit.
+    /// Prove (i.e. sign) a single block, or a batch of blocks, and exit.
     OneShot(OneShotArgs),
     /// Bootstrap the application and then exit. The bootstrapping process generates the
     /// initial public-private key pair and stores it on the disk in an encrypted
@@ -27,6 +27,12 @@ pub enum Command {
 pub struct OneShotArgs {
     #[clap(long)]
     pub sgx_instance_id: u32,
+    #[clap(long)]
+    /// Path to the bincode encoded input. The input is read from stdin when not set.
+    pub input_path: Option<PathBuf>,
+    #[clap(long)]
+    /// The input is a batch of blocks that are proven together.
+    pub batch: bool,
 }
 
 fn get_default_raiko_user_config_path(subdir: &str) -> PathBuf {
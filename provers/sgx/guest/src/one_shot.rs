@@ -1,6 +1,6 @@
 use std::{
     fs::{self, File, OpenOptions},
-    io::prelude::*,
+    io::{prelude::*, BufReader},
     os::unix::fs::PermissionsExt,
     path::{Path, PathBuf},
 };
@@ -8,12 +8,15 @@ use std::{
 use anyhow::{anyhow, bail, Context, Error, Result};
 use base64_serde::base64_serde_type;
 use raiko_lib::{
-    builder::{BlockBuilderStrategy, TaikoStrategy},
-    protocol_instance::{assemble_protocol_instance, EvidenceType},
+    builder::{build_batch, build_block},
+    input::{GuestBatchInput, GuestInput},
+    protocol_instance::{
+        assemble_batch_protocol_instance, assemble_protocol_instance, EvidenceType,
+    },
 };
 use raiko_primitives::Address;
 use secp256k1::{KeyPair, SecretKey};
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 base64_serde_type!(Base64Standard, base64::engine::general_purpose::STANDARD);
 
 use crate::{
@@ -112,17 +115,30 @@ pub async fn one_shot(global_opts: GlobalOpts, args: OneShotArgs) -> Result<()>
     let new_pubkey = public_key(&prev_privkey);
     let new_instance = public_key_to_address(&new_pubkey);
 
-    let input = bincode::deserialize_from(std::io::stdin()).expect("unable to deserialize input");
+    let evidence_type = EvidenceType::Sgx {
+        new_pubkey: new_instance,
+    };
+    let pi_hash = if args.batch {
+        let input: GuestBatchInput =
+            read_input(args.input_path.as_deref()).expect("unable to deserialize input");
 
-    // Process the block
-    let (header, _mpt_node) =
-        TaikoStrategy::build_from(&input).expect("Failed to build the resulting block");
+        // Process all the blocks of the batch
+        let headers = build_batch(&input).expect("Failed to build the resulting blocks");
 
-    // Calculate the public input hash
-    let pi = assemble_protocol_instance(&input, &header)?;
-    let pi_hash = pi.instance_hash(EvidenceType::Sgx {
-        new_pubkey: new_instance,
-    });
+        // Calculate the public input hash of the whole batch
+        let pi = assemble_batch_protocol_instance(&input, &headers)?;
+        pi.instance_hash(evidence_type)
+    } else {
+        let input: GuestInput =
+            read_input(args.input_path.as_deref()).expect("unable to deserialize input");
+
+        // Process the block
+        let (header, _mpt_node) = build_block(&input).expect("Failed to build the resulting block");
+
+        // Calculate the public input hash
+        let pi = assemble_protocol_instance(&input, &header)?;
+        pi.instance_hash(evidence_type)
+    };
 
     println!("Data to be signed: {pi_hash}");
 
@@ -154,6 +170,19 @@ pub async fn one_shot(global_opts: GlobalOpts, args: OneShotArgs) -> Result<()>
     print_sgx_info()
 }
 
+/// Reads the bincode encoded input from the given file, or from stdin when not set.
+fn read_input<T: DeserializeOwned>(input_path: Option<&Path>) -> Result<T> {
+    let input = match input_path {
+        Some(path) => {
+            let file = File::open(path)
+                .with_context(|| format!("Failed to open input file {}", path.display()))?;
+            bincode::deserialize_from(BufReader::new(file))
+        }
+        None => bincode::deserialize_from(std::io::stdin()),
+    }?;
+    Ok(input)
+}
+
 fn load_bootstrap(secrets_dir: &Path) -> Result<SecretKey, Error> {
     let privkey_path = secrets_dir.join(PRIV_KEY_FILENAME);
     if privkey_path.is_file() && !privkey_path.metadata().unwrap().permissions().readonly() {
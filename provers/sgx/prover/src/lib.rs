@@ -1,23 +1,25 @@
 #![cfg(feature = "enable")]
 use std::{
     env,
-    fs::{copy, create_dir_all, remove_file},
-    path::PathBuf,
-    process::{Command as StdCommand, Output, Stdio},
+    fs::{copy, create_dir_all, read, read_to_string, remove_file},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Command as StdCommand, Output},
     str,
 };
 
 use alloy_sol_types::SolValue;
 use once_cell::sync::Lazy;
 use raiko_lib::{
-    input::{GuestInput, GuestOutput},
-    protocol_instance::ProtocolInstance,
+    input::{GuestBatchInput, GuestInput, GuestOutput},
+    protocol_instance::{BatchProtocolInstance, EvidenceType, ProtocolInstance},
     prover::{to_proof, Proof, Prover, ProverConfig, ProverError, ProverResult},
 };
-use raiko_primitives::{keccak::keccak, B256};
+use raiko_primitives::{keccak::keccak, Address, B256};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_with::serde_as;
+use tempfile::NamedTempFile;
 use tokio::{process::Command, sync::OnceCell};
 
 #[serde_as]
@@ -27,6 +29,10 @@ pub struct SgxParam {
     pub setup: bool,
     pub bootstrap: bool,
     pub prove: bool,
+    /// Directory the input is handed to the enclave through, `inputs` next to the guest
+    /// by default. Changing it requires running the setup again.
+    #[serde(default)]
+    pub input_path: Option<PathBuf>,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize)]
@@ -38,90 +44,44 @@ pub struct SgxResponse {
 }
 
 pub const ELF_NAME: &str = "sgx-guest";
+/// The directory the input directory is mounted on inside the enclave
+pub const ENCLAVE_INPUT_DIR: &str = "/root/.config/raiko/inputs";
 pub const CONFIG: &str = if cfg!(feature = "docker_build") {
     "../provers/sgx/config"
 } else {
     "../../provers/sgx/config"
 };
 
+/// Offset of ENCLAVEHASH in the SIGSTRUCT written by `gramine-sgx-sign`
+const SIGSTRUCT_MR_ENCLAVE_OFFSET: usize = 960;
+
 static GRAMINE_MANIFEST_TEMPLATE: Lazy<OnceCell<PathBuf>> = Lazy::new(OnceCell::new);
 static PRIVATE_KEY: Lazy<OnceCell<PathBuf>> = Lazy::new(OnceCell::new);
 
 pub struct SgxProver;
 
+/// What the enclave is asked to prove.
+#[derive(Clone, Copy)]
+enum SgxInput<'a> {
+    Block(&'a GuestInput),
+    Batch(&'a GuestBatchInput),
+}
+
 impl Prover for SgxProver {
     async fn run(
-        input: GuestInput,
+        input: &GuestInput,
         _output: GuestOutput,
         config: &ProverConfig,
     ) -> ProverResult<Proof> {
-        let config = SgxParam::deserialize(config.get("sgx").unwrap()).unwrap();
-
-        // Support both SGX and the direct backend for testing
-        let direct_mode = match env::var("SGX_DIRECT") {
-            Ok(value) => value == "1",
-            Err(_) => false,
-        };
-
-        println!(
-            "WARNING: running SGX in {} mode!",
-            if direct_mode {
-                "direct (a.k.a. simulation)"
-            } else {
-                "hardware"
-            }
-        );
-
-        // The working directory
-        let cur_dir = env::current_exe()
-            .expect("Fail to get current directory")
-            .parent()
-            .unwrap()
-            .to_path_buf();
-        println!("Current directory: {:?}\n", cur_dir);
-        // Working paths
-        PRIVATE_KEY
-            .get_or_init(|| async { cur_dir.join("secrets").join("priv.key") })
-            .await;
-        GRAMINE_MANIFEST_TEMPLATE
-            .get_or_init(|| async {
-                cur_dir
-                    .join(CONFIG)
-                    .join("sgx-guest.local.manifest.template")
-            })
-            .await;
-
-        // The gramine command (gramine or gramine-direct for testing in non-SGX environment)
-        let gramine_cmd = || -> StdCommand {
-            let mut cmd = if direct_mode {
-                StdCommand::new("gramine-direct")
-            } else {
-                let mut cmd = StdCommand::new("sudo");
-                cmd.arg("gramine-sgx");
-                cmd
-            };
-            cmd.current_dir(&cur_dir).arg(ELF_NAME);
-            cmd
-        };
-
-        // Setup: run this once while setting up your SGX instance
-        if config.setup {
-            setup(&cur_dir, direct_mode).await?;
-        }
-
-        let mut sgx_proof = if config.bootstrap {
-            bootstrap(cur_dir.clone(), gramine_cmd()).await
-        } else {
-            // Dummy proof: it's ok when only setup/bootstrap was requested
-            Ok(SgxResponse::default())
-        };
-
-        if config.prove {
-            // overwirte sgx_proof as the bootstrap quote stays the same in bootstrap & prove.
-            sgx_proof = prove(gramine_cmd(), input.clone(), config.instance_id).await
-        }
+        run_sgx(SgxInput::Block(input), config).await
+    }
 
-        to_proof(sgx_proof)
+    async fn run_batch(
+        input: &GuestBatchInput,
+        _output: GuestOutput,
+        config: &ProverConfig,
+    ) -> ProverResult<Proof> {
+        run_sgx(SgxInput::Batch(input), config).await
     }
 
     fn instance_hash(pi: ProtocolInstance) -> B256 {
@@ -137,14 +97,112 @@ impl Prover for SgxProver {
 
         keccak(data).into()
     }
+
+    // The same hash the enclave signs. The enclave computes it itself, this is only the output
+    // of the test run on the host, with a zero instance when it wasn't bootstrapped.
+    fn batch_instance_hash(pi: BatchProtocolInstance) -> B256 {
+        pi.instance_hash(EvidenceType::Sgx {
+            new_pubkey: instance_address().unwrap_or_default(),
+        })
+    }
+}
+
+/// The address of the instance key, read from the `bootstrap.json` written by the bootstrap.
+fn instance_address() -> Option<Address> {
+    let cur_dir = env::current_exe().ok()?.parent()?.to_path_buf();
+    let bootstrap = read_to_string(cur_dir.join("config").join("bootstrap.json")).ok()?;
+    let bootstrap: Value = serde_json::from_str(&bootstrap).ok()?;
+    Address::deserialize(bootstrap.get("new_instance")?).ok()
+}
+
+/// The MRENCLAVE of the signed guest, read from the `sgx-guest.sig` generated by the setup.
+/// Not available before the setup was run on an SGX machine.
+pub fn mr_enclave() -> Option<B256> {
+    let cur_dir = env::current_exe().ok()?.parent()?.to_path_buf();
+    let sig = read(cur_dir.join(format!("{ELF_NAME}.sig"))).ok()?;
+    sig.get(SIGSTRUCT_MR_ENCLAVE_OFFSET..SIGSTRUCT_MR_ENCLAVE_OFFSET + 32)
+        .map(B256::from_slice)
+}
+
+async fn run_sgx(input: SgxInput<'_>, config: &ProverConfig) -> ProverResult<Proof> {
+    let config = SgxParam::deserialize(config.get("sgx").unwrap()).unwrap();
+
+    // Support both SGX and the direct backend for testing
+    let direct_mode = match env::var("SGX_DIRECT") {
+        Ok(value) => value == "1",
+        Err(_) => false,
+    };
+
+    println!(
+        "WARNING: running SGX in {} mode!",
+        if direct_mode {
+            "direct (a.k.a. simulation)"
+        } else {
+            "hardware"
+        }
+    );
+
+    // The working directory
+    let cur_dir = env::current_exe()
+        .expect("Fail to get current directory")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    println!("Current directory: {:?}\n", cur_dir);
+    // Working paths
+    PRIVATE_KEY
+        .get_or_init(|| async { cur_dir.join("secrets").join("priv.key") })
+        .await;
+    GRAMINE_MANIFEST_TEMPLATE
+        .get_or_init(|| async {
+            cur_dir
+                .join(CONFIG)
+                .join("sgx-guest.local.manifest.template")
+        })
+        .await;
+
+    let input_dir = cur_dir.join(config.input_path.as_deref().unwrap_or(Path::new("inputs")));
+
+    // The gramine command (gramine or gramine-direct for testing in non-SGX environment)
+    let gramine_cmd = || -> StdCommand {
+        let mut cmd = if direct_mode {
+            StdCommand::new("gramine-direct")
+        } else {
+            let mut cmd = StdCommand::new("sudo");
+            cmd.arg("gramine-sgx");
+            cmd
+        };
+        cmd.current_dir(&cur_dir).arg(ELF_NAME);
+        cmd
+    };
+
+    // Setup: run this once while setting up your SGX instance
+    if config.setup {
+        setup(&cur_dir, &input_dir, direct_mode).await?;
+    }
+
+    let mut sgx_proof = if config.bootstrap {
+        bootstrap(cur_dir.clone(), gramine_cmd()).await
+    } else {
+        // Dummy proof: it's ok when only setup/bootstrap was requested
+        Ok(SgxResponse::default())
+    };
+
+    if config.prove {
+        // overwirte sgx_proof as the bootstrap quote stays the same in bootstrap & prove.
+        sgx_proof = prove(gramine_cmd(), input, &input_dir, config.instance_id).await
+    }
+
+    to_proof(sgx_proof)
 }
 
-async fn setup(cur_dir: &PathBuf, direct_mode: bool) -> ProverResult<(), String> {
+async fn setup(cur_dir: &PathBuf, input_dir: &Path, direct_mode: bool) -> ProverResult<(), String> {
     // Create required directories
     let directories = ["secrets", "config"];
     for dir in directories {
         create_dir_all(cur_dir.join(dir)).unwrap();
     }
+    create_dir_all(input_dir).unwrap();
     if direct_mode {
         // Copy dummy files in direct mode
         let files = ["attestation_type", "quote", "user_report_data"];
@@ -167,6 +225,7 @@ async fn setup(cur_dir: &PathBuf, direct_mode: bool) -> ProverResult<(), String>
             "-Ddirect_mode={}",
             if direct_mode { "1" } else { "0" }
         ))
+        .arg(format!("-Dinput_dir={}", input_dir.display()))
         .arg(GRAMINE_MANIFEST_TEMPLATE.get().unwrap())
         .arg("sgx-guest.manifest")
         .output()
@@ -229,30 +288,57 @@ async fn bootstrap(
 
 async fn prove(
     mut gramine_cmd: StdCommand,
-    input: GuestInput,
+    input: SgxInput<'_>,
+    input_dir: &Path,
     instance_id: u64,
 ) -> ProverResult<SgxResponse, ProverError> {
-    tokio::task::spawn_blocking(move || {
-        let mut child = gramine_cmd
+    // Hand the input over through a file so the enclave streams it from disk instead of the
+    // host keeping another copy of it around while the guest is running. Every job gets its own
+    // file, which is removed when it is dropped, also when the guest task fails.
+    let input_file = match input {
+        SgxInput::Block(input) => write_input(input, input_dir)?,
+        SgxInput::Batch(input) => write_input(input, input_dir)?,
+    };
+    let enclave_input_path =
+        Path::new(ENCLAVE_INPUT_DIR).join(input_file.path().file_name().unwrap());
+    let is_batch = matches!(input, SgxInput::Batch(_));
+
+    let res = tokio::task::spawn_blocking(move || {
+        gramine_cmd
             .arg("one-shot")
             .arg("--sgx-instance-id")
             .arg(instance_id.to_string())
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| format!("Could not spawn gramine cmd: {}", e))?;
-        let stdin = child.stdin.as_mut().expect("Failed to open stdin");
-        bincode::serialize_into(stdin, &input).expect("Unable to serialize input");
-
-        let output = child
-            .wait_with_output()
+            .arg("--input-path")
+            .arg(enclave_input_path);
+        if is_batch {
+            gramine_cmd.arg("--batch");
+        }
+        let output = gramine_cmd
+            .output()
             .map_err(|e| handle_gramine_error("Could not run SGX guest prover", e))?;
         handle_output(&output, "SGX prove")?;
         Ok(parse_sgx_result(output.stdout)?)
     })
-    .await
-    .map_err(|e| ProverError::GuestError(e.to_string()))?
+    .await;
+
+    if let Err(e) = input_file.close() {
+        println!("Error deleting input file: {}", e);
+    }
+    res.map_err(|e| ProverError::GuestError(e.to_string()))?
+}
+
+fn write_input(input: &impl Serialize, input_dir: &Path) -> ProverResult<NamedTempFile, String> {
+    create_dir_all(input_dir).map_err(|e| format!("Could not create input directory: {}", e))?;
+    let mut file = NamedTempFile::new_in(input_dir)
+        .map_err(|e| format!("Could not create input file: {}", e))?;
+    let mut writer = BufWriter::new(&mut file);
+    bincode::serialize_into(&mut writer, input)
+        .map_err(|e| format!("Unable to serialize input: {}", e))?;
+    writer
+        .flush()
+        .map_err(|e| format!("Could not write input file: {}", e))?;
+    drop(writer);
+    Ok(file)
 }
 
 fn parse_sgx_result(output: Vec<u8>) -> ProverResult<SgxResponse, String> {
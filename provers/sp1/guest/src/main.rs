@@ -4,13 +4,13 @@ sp1_zkvm::entrypoint!(main);
 use raiko_lib::protocol_instance::assemble_protocol_instance;
 use raiko_lib::protocol_instance::EvidenceType;
 use raiko_lib::{
-    builder::{BlockBuilderStrategy, TaikoStrategy},
+    builder::build_block,
     input::{GuestInput, GuestOutput, WrappedHeader},
 };
 
 pub fn main() {
     let input = sp1_zkvm::io::read::<GuestInput>();
-    let build_result = TaikoStrategy::build_from(&input);
+    let build_result = build_block(&input);
 
     let output = match &build_result {
         Ok((header, mpt_node)) => {
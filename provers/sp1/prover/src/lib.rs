@@ -6,14 +6,14 @@ use alloy_primitives::B256;
 use alloy_sol_types::SolValue;
 use raiko_lib::{
     input::{GuestInput, GuestOutput},
-    protocol_instance::ProtocolInstance,
+    protocol_instance::{BatchProtocolInstance, EvidenceType, ProtocolInstance},
     prover::{to_proof, Proof, Prover, ProverConfig, ProverResult},
 };
 use serde::{Deserialize, Serialize};
 use sha3::{self, Digest};
 use sp1_sdk::{utils, ProverClient, SP1Stdin};
 
-const ELF: &[u8] = include_bytes!("../../guest/elf/riscv32im-succinct-zkvm-elf");
+pub const ELF: &[u8] = include_bytes!("../../guest/elf/riscv32im-succinct-zkvm-elf");
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Sp1Response {
@@ -25,13 +25,14 @@ pub struct Sp1Prover;
 
 impl Prover for Sp1Prover {
     async fn run(
-        input: GuestInput,
+        input: &GuestInput,
         _output: GuestOutput,
         _config: &ProverConfig,
     ) -> ProverResult<Proof> {
-        // Write the input.
+        // Write the input. The SDK only takes the input from memory, so unlike SGX and local
+        // risc0 proving this is still a serialized copy of the input.
         let mut stdin = SP1Stdin::new();
-        stdin.write(&input);
+        stdin.write(input);
 
         // Generate the proof for the given program.
         let client = ProverClient::new();
@@ -70,4 +71,8 @@ impl Prover for Sp1Prover {
         let hash: [u8; 32] = sha3::Keccak256::digest(data).into();
         hash.into()
     }
+
+    fn batch_instance_hash(pi: BatchProtocolInstance) -> B256 {
+        pi.instance_hash(EvidenceType::Succinct)
+    }
 }